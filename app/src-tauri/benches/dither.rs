@@ -0,0 +1,52 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use matrix_maze::dither::bayer::BayerPatterns;
+use matrix_maze::dither::lut::DitherLut;
+use matrix_maze::dither::DitherPattern;
+
+// A spread of UVs, like one row of wall pixels
+fn sample_uvs() -> Vec<(f64, f64)> {
+    (0..256).map(|i| (i as f64 * 0.0137, i as f64 * 0.0071 + 0.3)).collect()
+}
+
+fn bench_sample(c: &mut Criterion) {
+    let pattern = DitherPattern::new();
+    let patterns = BayerPatterns::new();
+    let lut = DitherLut::new(&patterns);
+    let uvs = sample_uvs();
+    
+    let mut group = c.benchmark_group("dither_sample");
+    for level in 0..4 {
+        let dot_count = patterns.get_level(level).len();
+        group.bench_with_input(BenchmarkId::new("search", level), &level, |b, &level| {
+            b.iter(|| {
+                uvs.iter()
+                    .map(|&uv| pattern.sample_pattern(black_box(uv), level, dot_count))
+                    .sum::<f64>()
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("lookup", level), &level, |b, &level| {
+            b.iter(|| {
+                uvs.iter()
+                    .map(|&uv| lut.sample(black_box(uv), level, dot_count))
+                    .sum::<f64>()
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_dither(c: &mut Criterion) {
+    let pattern = DitherPattern::shared();
+    let uvs = sample_uvs();
+    
+    c.bench_function("dither_interpolated", |b| {
+        b.iter(|| {
+            uvs.iter()
+                .map(|&uv| pattern.dither(black_box(0.4), uv, black_box(0.6)))
+                .sum::<f64>()
+        });
+    });
+}
+
+criterion_group!(benches, bench_sample, bench_dither);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use matrix_maze::clock::MockClock;
+use matrix_maze::game::GameState;
+use matrix_maze::maze::Maze;
+use matrix_maze::parallel::Threading;
+
+// Terminal-sized, large window, and fullscreen on a big monitor
+const RESOLUTIONS: [(usize, usize); 3] = [(120, 40), (320, 90), (640, 180)];
+// The same level-1-sized maze every run, so results can be compared
+const SEED: u64 = 0x5eed;
+
+fn bench_render_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_frame");
+    let maze = Maze::with_seed(8, 8, SEED);
+    // Late enough that the start message is gone
+    let clock = MockClock::new(10.0);
+    
+    for (width, height) in RESOLUTIONS {
+        let label = format!("{}x{}", width, height);
+        for (name, threading) in [("single", Threading::Single), ("parallel", Threading::Parallel)] {
+            let state = GameState::with_maze(&maze);
+            group.bench_with_input(BenchmarkId::new(name, &label), &threading, |b, &threading| {
+                b.iter(|| state.render_frame_with(width, height, threading, &clock));
+            });
+        }
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, bench_render_frame);
+criterion_main!(benches);
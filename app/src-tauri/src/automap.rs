@@ -0,0 +1,78 @@
+use crate::game::GameState;
+use crate::overlay::{Anchor, Layer, Overlay};
+use std::f64::consts::PI;
+
+// How map cells are drawn once they've been seen; cells that haven't stay blank
+const UNSEEN: char = ' ';
+const WALL: char = '█';
+const FLOOR: char = '·';
+const EXIT: char = 'X';
+
+/// What the map shows for cell (`x`, `y`) of `maze`: nothing until the
+/// player has seen it, then its wall or floor, or the exit.
+pub fn cell_glyph(game: &GameState, x: i64, y: i64) -> char {
+    if x < 0 || y < 0 {
+        return UNSEEN;
+    }
+    let (x, y) = (x as usize, y as usize);
+    if !game.seen.get(y).and_then(|row| row.get(x)).copied().unwrap_or(false) {
+        return UNSEEN;
+    }
+    if (x, y) == game.maze.exit {
+        EXIT
+    } else if game.maze.cells[y][x] {
+        WALL
+    } else {
+        FLOOR
+    }
+}
+
+/// An arrow pointing the way `angle` faces, to the nearest eighth of a turn.
+pub fn arrow(angle: f64) -> char {
+    ['→', '↘', '↓', '↙', '←', '↖', '↑', '↗'][((angle / (PI / 4.0)).round() as i64).rem_euclid(8) as usize]
+}
+
+/// The map of everything explored so far, filling a frame: the corridors
+/// and walls the player has seen, the player as an arrow, and the exit once
+/// it's been found.
+pub fn render(game: &GameState, width: usize, height: usize) -> String {
+    let maze = &game.maze;
+    // Character cells per map cell, twice as many across as down so cells
+    // come out square, leaving room for the title and hint
+    let rows = (height.saturating_sub(4) / maze.height.max(1)).min(width / 2 / maze.width.max(1)).max(1);
+    let cols = rows * 2;
+    let mut grid: Vec<Vec<char>> = (0..maze.height * rows)
+        .map(|row| (0..maze.width * cols).map(|col| cell_glyph(game, (col / cols) as i64, (row / rows) as i64)).collect())
+        .collect();
+    
+    let (x, y) = game.map_position();
+    let row = ((y * rows as f64) as usize).min(grid.len().saturating_sub(1));
+    if let Some(cell) = grid.get_mut(row).and_then(|line| line.get_mut((x * cols as f64) as usize)) {
+        *cell = arrow(game.player_angle);
+    }
+    
+    let map = Layer::new(grid.iter().map(|line| line.iter().collect::<String>()));
+    let title = Layer { anchor: Anchor::Top, ..Layer::new([format!("LEVEL {} - MAP", game.current_level)]) };
+    let hint = Layer { anchor: Anchor::Bottom, ..Layer::new(["M closes the map"]) };
+    Overlay { layers: vec![map, title, hint] }.apply(&vec![" ".repeat(width); height].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+    
+    #[test]
+    fn test_only_seen_cells_are_mapped() {
+        let game = GameState::with_maze(&Maze::with_seed(8, 8, 0x5eed));
+        let (x, y) = (game.player_x as i64, game.player_y as i64);
+        // What's in view from the start is already seen, and what's behind isn't
+        assert_eq!(cell_glyph(&game, x, y), FLOOR);
+        assert!(game.seen.iter().flatten().any(|&seen| !seen));
+        assert_eq!(cell_glyph(&game, -1, 0), UNSEEN);
+        
+        let map = render(&game, 40, 20);
+        assert_eq!(map.lines().count(), 20);
+        assert!(map.contains('→') && map.contains(WALL) && map.contains("M closes the map"));
+    }
+}
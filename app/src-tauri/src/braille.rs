@@ -0,0 +1,103 @@
+use crate::glyphs::{ink_coverage, Glyph, GlyphRamp};
+
+// First braille pattern (no dots raised); the low eight bits select dots
+const BRAILLE_BLANK: u32 = 0x2800;
+
+// Bit for the dot at (column, row) within a cell, per the Unicode braille layout
+const DOT_BITS: [[u32; 2]; 4] = [
+    [0x01, 0x08],
+    [0x02, 0x10],
+    [0x04, 0x20],
+    [0x40, 0x80],
+];
+
+// Ordered-dither thresholds for the eight dots of a cell, spread so partial
+// coverage lights dots evenly instead of filling from one corner
+const DOT_THRESHOLDS: [[f64; 2]; 4] = [
+    [0.5 / 8.0, 4.5 / 8.0],
+    [6.5 / 8.0, 2.5 / 8.0],
+    [1.5 / 8.0, 5.5 / 8.0],
+    [7.5 / 8.0, 3.5 / 8.0],
+];
+
+/// The ramp used to shade individual braille dots.
+/// 
+/// Walls are either lit or not, so each dot shows the fractal dither directly.
+/// The floor is drawn as sparse dots: two per cell nearby, one further out.
+pub fn dot_ramp() -> GlyphRamp {
+    GlyphRamp::with_coverage(
+        "braille dots",
+        vec![Glyph { ch: ' ', coverage: 0.0 }, Glyph { ch: '█', coverage: 1.0 }],
+        vec!['░', '▁'],
+    )
+    .unwrap()
+}
+
+/// Packs a view shaded at twice the width and four times the height into
+/// braille characters, one per 2x4 block.
+/// 
+/// Each shaded pixel becomes a dot if its glyph's ink coverage beats the
+/// dot's ordered-dither threshold. Glyphs with no coverage data, like the
+/// bars of a grate, are treated as solid ink.
+/// 
+/// # Arguments
+/// 
+/// * `dots` - Shaded pixels, `height * 4` rows of `width * 2` characters
+/// * `width` - Output width in characters
+/// * `height` - Output height in characters
+pub fn pack(dots: &[Vec<char>], width: usize, height: usize) -> Vec<Vec<char>> {
+    let mut cells = vec![vec![' '; width]; height];
+    
+    for (row, line) in cells.iter_mut().enumerate() {
+        for (col, cell) in line.iter_mut().enumerate() {
+            let mut bits = 0;
+            for (dy, (row_bits, row_thresholds)) in DOT_BITS.iter().zip(DOT_THRESHOLDS.iter()).enumerate() {
+                for dx in 0..2 {
+                    let ch = dots
+                        .get(row * 4 + dy)
+                        .and_then(|sub_row| sub_row.get(col * 2 + dx))
+                        .copied()
+                        .unwrap_or(' ');
+                    let ink = ink_coverage(ch).unwrap_or(1.0);
+                    if ink > row_thresholds[dx] {
+                        bits |= row_bits[dx];
+                    }
+                }
+            }
+            // Leave empty cells as plain spaces so overlays and copy-paste look normal
+            if bits != 0 {
+                *cell = char::from_u32(BRAILLE_BLANK + bits).unwrap();
+            }
+        }
+    }
+    
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_pack_dot_layout() {
+        // Left column lit, right column dark, in a single cell
+        let dots = vec![vec!['█', ' ']; 4];
+        assert_eq!(pack(&dots, 1, 1), vec![vec!['⡇']]);
+        
+        let full = vec![vec!['█'; 4]; 8];
+        assert_eq!(pack(&full, 2, 2), vec![vec!['⣿'; 2]; 2]);
+        
+        let empty = vec![vec![' '; 2]; 4];
+        assert_eq!(pack(&empty, 1, 1), vec![vec![' ']]);
+    }
+    
+    #[test]
+    fn test_floor_dot_density() {
+        let ramp = dot_ramp();
+        let near = vec![vec![ramp.floor_glyph(0.0); 2]; 4];
+        let far = vec![vec![ramp.floor_glyph(0.5); 2]; 4];
+        let count = |cells: Vec<Vec<char>>| (cells[0][0] as u32 - BRAILLE_BLANK).count_ones();
+        assert_eq!(count(pack(&near, 1, 1)), 2);
+        assert_eq!(count(pack(&far, 1, 1)), 1);
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// A source of time for the game.
+/// 
+/// Readings are seconds on a monotonic timeline with an arbitrary origin, so
+/// only differences between them mean anything. They never go backwards,
+/// even if the system clock is changed.
+pub trait Clock {
+    fn now(&self) -> f64;
+}
+
+/// The real clock, measured from the first time any `SystemClock` is read in
+/// this process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+#[derive(Debug, Default)]
+pub struct MockClock {
+    seconds: AtomicU64, // f64 bits
+}
+
+impl MockClock {
+    pub fn new(seconds: f64) -> Self {
+        MockClock { seconds: AtomicU64::new(seconds.to_bits()) }
+    }
+    
+    /// Moves the clock forward by `seconds`.
+    pub fn advance(&self, seconds: f64) {
+        self.set(self.now() + seconds);
+    }
+    
+    pub fn set(&self, seconds: f64) {
+        self.seconds.store(seconds.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> f64 {
+        f64::from_bits(self.seconds.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_clocks() {
+        let mock = MockClock::new(10.0);
+        mock.advance(2.5);
+        assert_eq!(mock.now(), 12.5);
+        
+        let first = SystemClock.now();
+        assert!(SystemClock.now() >= first);
+    }
+}
@@ -0,0 +1,299 @@
+use crate::clock::SystemClock;
+use crate::dither::{DitherConfig, DitherMethod};
+use crate::error::GameError;
+use crate::flow::FlowKey;
+use crate::game::{MazeStyle, PlayerInput};
+use crate::renderer::RenderMode;
+use crate::replay::{PlaybackControl, Replay};
+use crate::session::{GameView, Session, SessionId, Sessions};
+use std::sync::Mutex;
+
+// What the app's Tauri commands do. `main.rs` only hands each one the
+// managed sessions and its arguments, so everything here can be tested
+// without a window.
+
+// Runs `f` on a session and returns its result, or an error if there's no
+// such session. Whatever the game did is then saved and published as events;
+// a save that fails is only reported as an event.
+fn with_session<T>(sessions: &Mutex<Sessions>, id: SessionId, f: impl FnOnce(&mut Session) -> Result<T, GameError>) -> Result<T, GameError> {
+    // A command that panicked mid-update leaves the session usable, if not tidy
+    let mut sessions = sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let session = sessions.session(id)?;
+    let result = f(session);
+    session.save();
+    sessions.publish(id);
+    result
+}
+
+pub fn init_game(sessions: &Mutex<Sessions>) -> GameView {
+    let mut sessions = sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let session = Session::new(&SystemClock, sessions.save_dir());
+    let id = sessions.open(session);
+    let session = sessions.get_mut(id).unwrap();
+    session.save();
+    let view = session.view(id);
+    sessions.publish(id);
+    view
+}
+
+pub fn close_game(sessions: &Mutex<Sessions>, session: SessionId) -> Result<(), GameError> {
+    let mut sessions = sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    sessions.close(session).map(|_| ()).ok_or_else(|| GameError::bad_state(format!("No game session {}", session)))
+}
+
+pub fn update_game(sessions: &Mutex<Sessions>, session: SessionId, input: PlayerInput) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.update(&input)?;
+        Ok(s.view(session))
+    })
+}
+
+pub fn render_frame(sessions: &Mutex<Sessions>, session: SessionId, width: usize, height: usize) -> Result<String, GameError> {
+    with_session(sessions, session, |s| s.render_frame(width, height, &SystemClock))
+}
+
+pub fn restart_game(sessions: &Mutex<Sessions>, session: SessionId) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        *s = Session::new(&SystemClock, s.save_dir.take());
+        Ok(s.view(session))
+    })
+}
+
+/// Forwards a key to the game flow, which decides what it does.
+pub fn press_key(sessions: &Mutex<Sessions>, session: SessionId, key: FlowKey) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.press(key, &SystemClock);
+        Ok(s.view(session))
+    })
+}
+
+pub fn race_replay(sessions: &Mutex<Sessions>, session: SessionId, replay: &str) -> Result<GameView, GameError> {
+    let replay: Replay = replay.parse()?;
+    with_session(sessions, session, |s| {
+        s.start(s.game.race(replay, &SystemClock));
+        Ok(s.view(session))
+    })
+}
+
+pub fn set_maze_style(sessions: &Mutex<Sessions>, session: SessionId, style: MazeStyle) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.start(s.game.with_maze_style(style, &SystemClock));
+        Ok(s.view(session))
+    })
+}
+
+pub fn set_glyph_ramp(sessions: &Mutex<Sessions>, session: SessionId, ramp: &str) -> Result<GameView, GameError> {
+    // `ramp` is a built-in name or a ramp definition; the current glyphs stay if it doesn't load
+    with_session(sessions, session, |s| {
+        s.set_glyph_ramp(ramp)?;
+        Ok(s.view(session))
+    })
+}
+
+pub fn set_render_mode(sessions: &Mutex<Sessions>, session: SessionId, mode: RenderMode) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.game.render_mode = mode;
+        Ok(s.view(session))
+    })
+}
+
+pub fn set_dither_method(sessions: &Mutex<Sessions>, session: SessionId, method: DitherMethod) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.game.dither_method = method;
+        Ok(s.view(session))
+    })
+}
+
+pub fn set_dither_config(sessions: &Mutex<Sessions>, session: SessionId, config: DitherConfig) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.game.dither_config = config.clamped();
+        Ok(s.view(session))
+    })
+}
+
+/// Saves the current level's replay and returns where it went.
+pub fn save_replay(sessions: &Mutex<Sessions>, session: SessionId) -> Result<String, GameError> {
+    // Cloned so the file is written without holding the lock
+    let replay = with_session(sessions, session, |s| Ok(s.game.replay.clone()))?;
+    let path = replay.default_path();
+    replay.save(&path)?;
+    Ok(path.display().to_string())
+}
+
+/// Plays the current level back from its start.
+pub fn watch_replay(sessions: &Mutex<Sessions>, session: SessionId) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.watch_level();
+        Ok(s.view(session))
+    })
+}
+
+pub fn load_replay(sessions: &Mutex<Sessions>, session: SessionId, replay: &str) -> Result<GameView, GameError> {
+    let replay: Replay = replay.parse()?;
+    with_session(sessions, session, |s| {
+        s.watch(replay);
+        Ok(s.view(session))
+    })
+}
+
+pub fn control_replay(sessions: &Mutex<Sessions>, session: SessionId, control: PlaybackControl) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.control_playback(control)?;
+        Ok(s.view(session))
+    })
+}
+
+pub fn stop_replay(sessions: &Mutex<Sessions>, session: SessionId) -> Result<GameView, GameError> {
+    with_session(sessions, session, |s| {
+        s.stop_watching();
+        Ok(s.view(session))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{GameEvent, Record, SessionEvent};
+    use crate::game::GameState;
+    use crate::test_mazes;
+    use std::sync::Arc;
+    
+    // One session on a fixed maze, so nothing is read from or written to disk
+    fn sessions() -> (Mutex<Sessions>, SessionId) {
+        let mut sessions = Sessions::new();
+        let id = sessions.open(Session::with_game(GameState::from_seed(1, MazeStyle::Blocks, 3)));
+        (Mutex::new(sessions), id)
+    }
+    
+    fn is_bad_state<T>(result: Result<T, GameError>) -> bool {
+        matches!(result, Err(GameError::BadState { .. }))
+    }
+    
+    #[test]
+    fn test_every_command_refuses_an_unknown_session() {
+        let (sessions, id) = sessions();
+        let missing = id + 1;
+        let replay = Replay::default().to_string();
+        assert!(is_bad_state(close_game(&sessions, missing)));
+        assert!(is_bad_state(update_game(&sessions, missing, PlayerInput::default())));
+        assert!(is_bad_state(render_frame(&sessions, missing, 40, 12)));
+        assert!(is_bad_state(restart_game(&sessions, missing)));
+        assert!(is_bad_state(press_key(&sessions, missing, FlowKey::Confirm)));
+        assert!(is_bad_state(race_replay(&sessions, missing, &replay)));
+        assert!(is_bad_state(set_maze_style(&sessions, missing, MazeStyle::ThinWalls)));
+        assert!(is_bad_state(set_glyph_ramp(&sessions, missing, "ascii")));
+        assert!(is_bad_state(set_render_mode(&sessions, missing, RenderMode::Braille)));
+        assert!(is_bad_state(set_dither_method(&sessions, missing, DitherMethod::Ordered)));
+        assert!(is_bad_state(set_dither_config(&sessions, missing, DitherConfig::default())));
+        assert!(is_bad_state(save_replay(&sessions, missing)));
+        assert!(is_bad_state(watch_replay(&sessions, missing)));
+        assert!(is_bad_state(load_replay(&sessions, missing, &replay)));
+        assert!(is_bad_state(control_replay(&sessions, missing, PlaybackControl::TogglePause)));
+        assert!(is_bad_state(stop_replay(&sessions, missing)));
+    }
+    
+    #[test]
+    fn test_corrupt_payloads_are_refused_and_leave_the_game_playable() {
+        let (sessions, id) = sessions();
+        let before = update_game(&sessions, id, PlayerInput::default()).unwrap();
+        
+        let nan_time = PlayerInput { forward: true, delta_time: f64::NAN, ..PlayerInput::default() };
+        assert!(is_bad_state(update_game(&sessions, id, nan_time)));
+        let infinite_mouse = PlayerInput { mouse_delta_x: f64::INFINITY, delta_time: 0.016, ..PlayerInput::default() };
+        assert!(is_bad_state(update_game(&sessions, id, infinite_mouse)));
+        
+        assert_eq!(render_frame(&sessions, id, 0, 12), Err(GameError::InvalidDimensions { width: 0, height: 12 }));
+        assert_eq!(render_frame(&sessions, id, 40, usize::MAX), Err(GameError::InvalidDimensions { width: 40, height: usize::MAX }));
+        
+        let unknown_level = "version = 1\nlevel = 0\nstyle = Blocks\nseed = 2a\n10 w\n";
+        assert_eq!(race_replay(&sessions, id, unknown_level), Err(GameError::UnknownLevel { level: 0 }));
+        assert_eq!(load_replay(&sessions, id, unknown_level), Err(GameError::UnknownLevel { level: 0 }));
+        assert!(is_bad_state(race_replay(&sessions, id, "{\"level\": 1}")));
+        assert!(is_bad_state(load_replay(&sessions, id, "\u{0}\u{1}garbage")));
+        
+        assert!(is_bad_state(set_glyph_ramp(&sessions, id, "not a ramp")));
+        assert!(is_bad_state(control_replay(&sessions, id, PlaybackControl::Speed(2.0))));
+        watch_replay(&sessions, id).unwrap();
+        assert!(is_bad_state(control_replay(&sessions, id, PlaybackControl::Speed(f64::NAN))));
+        stop_replay(&sessions, id).unwrap();
+        
+        // Out of range settings are pulled back in rather than refused
+        let config = DitherConfig { scale: f64::NAN, max_level: usize::MAX, ..DitherConfig::default() };
+        assert_eq!(set_dither_config(&sessions, id, config).unwrap().dither_config, config.clamped());
+        
+        // None of it got into the game, which still plays
+        let after = update_game(&sessions, id, PlayerInput { forward: true, delta_time: 0.1, ..PlayerInput::default() }).unwrap();
+        assert_eq!(after.glyph_ramp, before.glyph_ramp);
+        assert_eq!(after.playback, None);
+        {
+            let sessions = sessions.lock().unwrap();
+            let game = &sessions.get(id).unwrap().game;
+            assert!(game.player_x.is_finite() && game.player_angle.is_finite() && game.level_elapsed > 0.0);
+        }
+        assert!(!render_frame(&sessions, id, 40, 12).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_finishing_a_level_publishes_its_events() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut sessions = {
+            let received = received.clone();
+            Sessions::with_listener(move |event: &SessionEvent| received.lock().unwrap().push(event.clone()))
+        };
+        
+        // A 5x3 corridor with the exit two cells east of the start
+        let game = GameState::with_maze(&test_mazes::corridor(5, 3));
+        let idle = sessions.open(Session::with_game(game.clone()));
+        let id = sessions.open(Session::with_game(game));
+        let sessions = Mutex::new(sessions);
+        
+        let forward = PlayerInput { forward: true, delta_time: 0.1, ..PlayerInput::default() };
+        for _ in 0..20 {
+            update_game(&sessions, id, forward).unwrap();
+        }
+        update_game(&sessions, idle, PlayerInput::default()).unwrap();
+        
+        let received = received.lock().unwrap();
+        let time = sessions.lock().unwrap().get(id).unwrap().game.level_completion_time.unwrap();
+        assert_eq!(
+            *received,
+            vec![
+                SessionEvent { session: id, event: GameEvent::LevelCompleted { level: 1, time, record: true } },
+                SessionEvent { session: id, event: GameEvent::RecordBroken { record: Record::Level(1), time } },
+            ]
+        );
+    }
+    
+    #[test]
+    fn test_a_failed_save_is_reported_without_failing_the_command() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut sessions = {
+            let received = received.clone();
+            Sessions::with_listener(move |event: &SessionEvent| received.lock().unwrap().push(event.clone()))
+        };
+        
+        // A file where the save directory should be, so nothing can be saved
+        let not_a_dir = std::env::temp_dir().join(format!("matrix-maze-not-a-dir-{}", std::process::id()));
+        std::fs::write(&not_a_dir, "").unwrap();
+        let mut session = Session::with_game(GameState::with_maze(&test_mazes::corridor(5, 3)));
+        session.save_dir = Some(not_a_dir.clone());
+        let id = sessions.open(session);
+        let sessions = Mutex::new(sessions);
+        
+        let forward = PlayerInput { forward: true, delta_time: 0.1, ..PlayerInput::default() };
+        for _ in 0..20 {
+            update_game(&sessions, id, forward).unwrap();
+        }
+        std::fs::remove_file(&not_a_dir).unwrap();
+        
+        // The best times and the best run each fail to save, and the level is still finished
+        let failures = received
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| matches!(e.event, GameEvent::SaveFailed { error: GameError::Io { .. } }))
+            .count();
+        assert_eq!(failures, 2);
+        assert!(sessions.lock().unwrap().get(id).unwrap().game.has_won);
+    }
+}
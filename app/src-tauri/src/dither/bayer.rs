@@ -15,7 +15,7 @@
 /// 
 /// # Arguments
 /// 
-/// * `recursion` - The recursion level (0 = 1x1, 1 = 2x2, 2 = 4x4, 3 = 8x8)
+/// * `recursion` - The recursion level (0 = 1x1, 1 = 2x2, 2 = 4x4, 3 = 8x8, 4 = 16x16)
 /// 
 /// # Returns
 /// 
@@ -57,6 +57,7 @@ pub struct BayerPatterns {
     pub level_1: Vec<(f64, f64)>, // 2x2 (16 points)
     pub level_2: Vec<(f64, f64)>, // 4x4 (64 points)
     pub level_3: Vec<(f64, f64)>, // 8x8 (256 points)
+    pub level_4: Vec<(f64, f64)>, // 16x16 (256 points)
 }
 
 impl BayerPatterns {
@@ -67,6 +68,7 @@ impl BayerPatterns {
             level_1: generate_bayer_points(1),
             level_2: generate_bayer_points(2),
             level_3: generate_bayer_points(3),
+            level_4: generate_bayer_points(4),
         }
     }
     
@@ -74,7 +76,7 @@ impl BayerPatterns {
     /// 
     /// # Arguments
     /// 
-    /// * `level` - The fractal level (0-3)
+    /// * `level` - The fractal level (0-4)
     /// 
     /// # Returns
     /// 
@@ -85,7 +87,7 @@ impl BayerPatterns {
             1 => &self.level_1,
             2 => &self.level_2,
             3 => &self.level_3,
-            _ => &self.level_3, // Default to finest level
+            _ => &self.level_4, // Default to finest level
         }
     }
     
@@ -96,7 +98,7 @@ impl BayerPatterns {
             1 => 2,
             2 => 4,
             3 => 8,
-            _ => 8,
+            _ => 16,
         }
     }
 }
@@ -0,0 +1,153 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use super::config::DitherConfig;
+use super::ditherer::{quantize_with_threshold, Ditherer, Sample};
+use crate::parallel::{for_each_indexed, Threading};
+
+// Side length of the threshold map
+const SIZE: usize = 32;
+// Width of the Gaussian used to measure how clustered pixels are
+const SIGMA: f64 = 1.5;
+
+/// Threshold-map dithering with a blue-noise texture tiled over the screen.
+/// 
+/// Blue noise has no low-frequency structure, so it avoids the cross-hatch
+/// look of a Bayer matrix while staying just as cheap per pixel.
+pub struct BlueNoise {
+    thresholds: Vec<f64>, // SIZE x SIZE, row-major, each in [0.0, 1.0)
+}
+
+impl BlueNoise {
+    /// Builds the threshold map with the void-and-cluster method.
+    pub fn new() -> Self {
+        let ranks = void_and_cluster(SIZE, 0x5eed);
+        let count = (SIZE * SIZE) as f64;
+        Self {
+            thresholds: ranks.iter().map(|&r| (r as f64 + 0.5) / count).collect(),
+        }
+    }
+    
+    /// Threshold in [0.0, 1.0) for a screen position.
+    pub fn threshold(&self, x: usize, y: usize) -> f64 {
+        self.thresholds[(y % SIZE) * SIZE + x % SIZE]
+    }
+}
+
+impl Default for BlueNoise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ditherer for BlueNoise {
+    fn dither_frame(&self, rows: &mut [Vec<Option<Sample>>], levels: &[f64], _config: &DitherConfig, threading: Threading) {
+        for_each_indexed(rows, threading, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                if let Some(sample) = pixel {
+                    sample.brightness = quantize_with_threshold(sample.brightness, self.threshold(x, y), levels);
+                }
+            }
+        });
+    }
+}
+
+/// Ranks every pixel of a size x size torus so that the first n pixels of
+/// any prefix are as evenly spread as possible (Ulichney's void-and-cluster).
+fn void_and_cluster(size: usize, mut rng_seed: u64) -> Vec<usize> {
+    let n = size * size;
+    let kernel = gaussian_kernel(size);
+    
+    // Energy of each pixel: how crowded its neighbourhood is
+    let mut energy = vec![0.0; n];
+    let mut on = vec![false; n];
+    let toggle = |on: &mut [bool], energy: &mut [f64], p: usize| {
+        let sign = if on[p] { -1.0 } else { 1.0 };
+        on[p] = !on[p];
+        let (px, py) = (p % size, p / size);
+        for (q, e) in energy.iter_mut().enumerate() {
+            let dx = (q % size + size - px) % size;
+            let dy = (q / size + size - py) % size;
+            *e += sign * kernel[dy * size + dx];
+        }
+    };
+    
+    // Random initial pattern with a tenth of the pixels set
+    let initial = n / 10;
+    let mut placed = 0;
+    while placed < initial {
+        rng_seed = rng_seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let p = (rng_seed >> 33) as usize % n;
+        if !on[p] {
+            toggle(&mut on, &mut energy, p);
+            placed += 1;
+        }
+    }
+    
+    let tightest_cluster = |on: &[bool], energy: &[f64]| {
+        (0..n).filter(|&p| on[p]).max_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap()
+    };
+    let largest_void = |on: &[bool], energy: &[f64]| {
+        (0..n).filter(|&p| !on[p]).min_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap()
+    };
+    
+    // Move points from clusters into voids until the pattern settles
+    loop {
+        let cluster = tightest_cluster(&on, &energy);
+        toggle(&mut on, &mut energy, cluster);
+        let void = largest_void(&on, &energy);
+        if void == cluster {
+            toggle(&mut on, &mut energy, cluster);
+            break;
+        }
+        toggle(&mut on, &mut energy, void);
+    }
+    
+    let mut ranks = vec![0; n];
+    
+    // Rank the initial points by removing the tightest cluster each time
+    let (mut prototype_on, mut prototype_energy) = (on.clone(), energy.clone());
+    for rank in (0..initial).rev() {
+        let cluster = tightest_cluster(&prototype_on, &prototype_energy);
+        toggle(&mut prototype_on, &mut prototype_energy, cluster);
+        ranks[cluster] = rank;
+    }
+    
+    // Rank the rest by filling the largest void each time
+    for rank in initial..n {
+        let void = largest_void(&on, &energy);
+        toggle(&mut on, &mut energy, void);
+        ranks[void] = rank;
+    }
+    
+    ranks
+}
+
+// Gaussian weight for every toroidal offset (dx, dy)
+fn gaussian_kernel(size: usize) -> Vec<f64> {
+    let mut kernel = vec![0.0; size * size];
+    for dy in 0..size {
+        for dx in 0..size {
+            let wx = dx.min(size - dx) as f64;
+            let wy = dy.min(size - dy) as f64;
+            kernel[dy * size + dx] = (-(wx * wx + wy * wy) / (2.0 * SIGMA * SIGMA)).exp();
+        }
+    }
+    kernel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_every_threshold_used_once() {
+        let noise = BlueNoise::new();
+        let mut ranks: Vec<usize> = noise.thresholds.iter().map(|t| (t * (SIZE * SIZE) as f64) as usize).collect();
+        ranks.sort();
+        assert_eq!(ranks, (0..SIZE * SIZE).collect::<Vec<_>>());
+    }
+}
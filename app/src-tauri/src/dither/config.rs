@@ -0,0 +1,102 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use serde::{Deserialize, Serialize};
+
+// Finest fractal level there are Bayer patterns for (16x16)
+pub const MAX_LEVEL: usize = 4;
+
+/// How wall pixels are mapped to dither pattern coordinates.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum UvMapping {
+    #[default]
+    Surface, // Position along the wall face and height above the floor; the pattern is painted on
+    WorldRow, // Hit position along the wall plus the screen row
+    World, // Hit position only; the pattern is constant down each column
+    Screen, // Screen column and row; the pattern doesn't move with the walls
+}
+
+/// Tuning knobs for the fractal dither.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct DitherConfig {
+    pub scale: f64, // Pattern tiles per world unit on the wall
+    pub row_scale: f64, // Pattern tiles per screen row, and per column in the Screen mapping
+    pub level_bias: f64, // Added to the fractal level; positive favours finer patterns
+    pub contrast: f64, // Brightness is stretched around 0.5 by this factor before dithering
+    pub dot_scale: f64, // Dot falloff in multiples of the dot radius; larger means softer, bigger dots
+    pub max_level: usize, // Finest recursion level, 0 (1x1) to 4 (16x16)
+    pub uv_mapping: UvMapping,
+}
+
+impl Default for DitherConfig {
+    fn default() -> Self {
+        Self {
+            scale: 2.0,
+            row_scale: 0.1,
+            level_bias: 0.0,
+            contrast: 1.0,
+            dot_scale: 2.4,
+            max_level: 3,
+            uv_mapping: UvMapping::Surface,
+        }
+    }
+}
+
+impl DitherConfig {
+    /// Returns a copy with every field pulled into its usable range.
+    pub fn clamped(self) -> Self {
+        let finite_or = |value: f64, default: f64| if value.is_finite() { value } else { default };
+        let defaults = Self::default();
+        Self {
+            scale: finite_or(self.scale, defaults.scale).clamp(0.01, 100.0),
+            row_scale: finite_or(self.row_scale, defaults.row_scale).clamp(0.0, 10.0),
+            level_bias: finite_or(self.level_bias, defaults.level_bias).clamp(-(MAX_LEVEL as f64), MAX_LEVEL as f64),
+            contrast: finite_or(self.contrast, defaults.contrast).clamp(0.0, 10.0),
+            dot_scale: finite_or(self.dot_scale, defaults.dot_scale).clamp(0.5, 10.0),
+            max_level: self.max_level.min(MAX_LEVEL),
+            uv_mapping: self.uv_mapping,
+        }
+    }
+    
+    /// Maps a wall pixel to pattern coordinates.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `hit` - World-space hit position on the wall
+    /// * `surface` - Position along the wall face and height above the
+    ///   floor, both in world units
+    /// * `screen` - Screen column and row of the pixel
+    pub fn uv(&self, hit: (f64, f64), surface: (f64, f64), screen: (f64, f64)) -> (f64, f64) {
+        match self.uv_mapping {
+            UvMapping::Surface => (surface.0 * self.scale, surface.1 * self.scale),
+            UvMapping::WorldRow => (hit.0 * self.scale, hit.1 * self.scale + screen.1 * self.row_scale),
+            UvMapping::World => (hit.0 * self.scale, hit.1 * self.scale),
+            UvMapping::Screen => (screen.0 * self.row_scale, screen.1 * self.row_scale),
+        }
+    }
+    
+    /// Applies the contrast setting to a brightness in [0.0, 1.0].
+    pub fn apply_contrast(&self, brightness: f64) -> f64 {
+        ((brightness - 0.5) * self.contrast + 0.5).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_clamped_and_partial_json() {
+        // Missing fields fall back to defaults, so the frontend can send only what it changes
+        let config: DitherConfig = serde_json::from_str(r#"{"max_level": 9, "contrast": -2.0}"#).unwrap();
+        let config = config.clamped();
+        assert_eq!(config.max_level, MAX_LEVEL);
+        assert_eq!(config.contrast, 0.0);
+        assert_eq!(config.scale, DitherConfig::default().scale);
+        assert_eq!(config.apply_contrast(0.9), 0.5);
+    }
+}
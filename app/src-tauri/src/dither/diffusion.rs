@@ -0,0 +1,70 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use super::config::DitherConfig;
+use super::ditherer::{quantize_nearest, Ditherer, Sample};
+use crate::parallel::Threading;
+
+/// Error-diffusion dithering: each pixel is snapped to the nearest level and
+/// the rounding error is pushed onto neighbours not yet visited.
+/// 
+/// Error only flows between shaded pixels, so it doesn't leak across the
+/// edge of a wall into the sky or floor.
+pub struct ErrorDiffusion {
+    weights: &'static [(isize, usize, f64)], // (dx, dy, share of the error)
+}
+
+impl ErrorDiffusion {
+    /// Floyd–Steinberg: all of the error, spread over four neighbours.
+    pub const FLOYD_STEINBERG: ErrorDiffusion = ErrorDiffusion {
+        weights: &[
+            (1, 0, 7.0 / 16.0),
+            (-1, 1, 3.0 / 16.0),
+            (0, 1, 5.0 / 16.0),
+            (1, 1, 1.0 / 16.0),
+        ],
+    };
+    
+    /// Atkinson: six neighbours get an eighth each, so a quarter of the error
+    /// is dropped and highlights and shadows stay crisp.
+    pub const ATKINSON: ErrorDiffusion = ErrorDiffusion {
+        weights: &[
+            (1, 0, 1.0 / 8.0),
+            (2, 0, 1.0 / 8.0),
+            (-1, 1, 1.0 / 8.0),
+            (0, 1, 1.0 / 8.0),
+            (1, 1, 1.0 / 8.0),
+            (0, 2, 1.0 / 8.0),
+        ],
+    };
+}
+
+impl Ditherer for ErrorDiffusion {
+    // Each row depends on the one above, so this always runs on one thread
+    fn dither_frame(&self, rows: &mut [Vec<Option<Sample>>], levels: &[f64], _config: &DitherConfig, _threading: Threading) {
+        for y in 0..rows.len() {
+            for x in 0..rows[y].len() {
+                let Some(sample) = rows[y][x].as_mut() else {
+                    continue;
+                };
+                let wanted = sample.brightness;
+                let shown = quantize_nearest(wanted, levels);
+                sample.brightness = shown;
+                let error = wanted - shown;
+                
+                for &(dx, dy, share) in self.weights {
+                    let nx = x as isize + dx;
+                    if nx < 0 {
+                        continue;
+                    }
+                    if let Some(Some(neighbour)) = rows.get_mut(y + dy).and_then(|row| row.get_mut(nx as usize)) {
+                        neighbour.brightness += error * share;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use super::blue_noise::BlueNoise;
+use super::config::DitherConfig;
+use super::diffusion::ErrorDiffusion;
+use super::ordered::OrderedBayer;
+use super::pattern::DitherPattern;
+use crate::parallel::{for_each_indexed, Threading};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// One shaded pixel of a surface, before dithering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub brightness: f64, // In [0.0, 1.0]; replaced by the dithered value
+    pub normalized_dist: f64, // Distance to the surface, 0.0 (close) to 1.0 (far)
+    pub uv: (f64, f64), // Surface coordinates, for patterns fixed to the world
+}
+
+/// A dithering algorithm.
+/// 
+/// A ditherer runs over a whole frame at once so that error diffusion can
+/// carry error between pixels. Pixels that aren't shaded surfaces - sky,
+/// floor, grate bars - are `None` and are left alone.
+pub trait Ditherer: Send + Sync {
+    /// Dithers every sample in place.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `rows` - Samples in screen order, one `Vec` per row
+    /// * `levels` - Brightness values the output can show, ascending; the
+    ///   glyph ramp turns each back into its glyph
+    /// * `config` - Fractal dither settings
+    /// * `threading` - Whether independent rows may be dithered in parallel
+    fn dither_frame(&self, rows: &mut [Vec<Option<Sample>>], levels: &[f64], config: &DitherConfig, threading: Threading);
+}
+
+/// The fractal method ported from Dither3D. Its pattern is stuck to surfaces,
+/// so it doesn't crawl as the camera moves.
+impl Ditherer for DitherPattern {
+    fn dither_frame(&self, rows: &mut [Vec<Option<Sample>>], _levels: &[f64], config: &DitherConfig, threading: Threading) {
+        for_each_indexed(rows, threading, |_, row| {
+            for sample in row.iter_mut().flatten() {
+                sample.brightness = self.dither_with(sample.normalized_dist, sample.uv, sample.brightness, config);
+            }
+        });
+    }
+}
+
+/// Dithering algorithms the renderer can switch between at runtime.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum DitherMethod {
+    #[default]
+    Fractal, // Surface-stable fractal Bayer dots (Dither3D)
+    Ordered, // Classic 8x8 Bayer matrix in screen space
+    BlueNoise, // Void-and-cluster threshold map in screen space
+    FloydSteinberg, // Error diffusion over the full frame
+    Atkinson, // Error diffusion that drops a quarter of the error, for more contrast
+}
+
+impl DitherMethod {
+    /// Every method, in the order the game cycles through them.
+    pub const ALL: [DitherMethod; 5] = [
+        DitherMethod::Fractal,
+        DitherMethod::Ordered,
+        DitherMethod::BlueNoise,
+        DitherMethod::FloydSteinberg,
+        DitherMethod::Atkinson,
+    ];
+    
+    /// Returns the shared ditherer for this method.
+    pub fn ditherer(self) -> &'static dyn Ditherer {
+        static ORDERED: OrderedBayer = OrderedBayer;
+        static BLUE_NOISE: OnceLock<BlueNoise> = OnceLock::new();
+        static FLOYD_STEINBERG: ErrorDiffusion = ErrorDiffusion::FLOYD_STEINBERG;
+        static ATKINSON: ErrorDiffusion = ErrorDiffusion::ATKINSON;
+        
+        match self {
+            DitherMethod::Fractal => DitherPattern::shared(),
+            DitherMethod::Ordered => &ORDERED,
+            DitherMethod::BlueNoise => BLUE_NOISE.get_or_init(BlueNoise::new),
+            DitherMethod::FloydSteinberg => &FLOYD_STEINBERG,
+            DitherMethod::Atkinson => &ATKINSON,
+        }
+    }
+}
+
+/// Snaps a brightness to one of the two levels around it, using a threshold
+/// in [0.0, 1.0) to decide how far between them it has to be to round up.
+pub fn quantize_with_threshold(brightness: f64, threshold: f64, levels: &[f64]) -> f64 {
+    let upper = levels.iter().position(|&l| l >= brightness).unwrap_or(levels.len() - 1);
+    if upper == 0 {
+        return levels[0];
+    }
+    let (low, high) = (levels[upper - 1], levels[upper]);
+    let t = (brightness - low) / (high - low);
+    if t > threshold {
+        high
+    } else {
+        low
+    }
+}
+
+/// Snaps a brightness to the nearest level.
+pub fn quantize_nearest(brightness: f64, levels: &[f64]) -> f64 {
+    quantize_with_threshold(brightness, 0.5, levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn flat_frame(brightness: f64, width: usize, height: usize) -> Vec<Vec<Option<Sample>>> {
+        let sample = Sample { brightness, normalized_dist: 0.5, uv: (0.0, 0.0) };
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| Some(Sample { uv: (x as f64 * 0.13, y as f64 * 0.17), ..sample }))
+                    .collect()
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_quantize() {
+        let levels = [0.0, 0.5, 1.0];
+        assert_eq!(quantize_nearest(0.2, &levels), 0.0);
+        assert_eq!(quantize_nearest(0.3, &levels), 0.5);
+        assert_eq!(quantize_with_threshold(0.6, 0.1, &levels), 1.0);
+        assert_eq!(quantize_with_threshold(0.6, 0.3, &levels), 0.5);
+        assert_eq!(quantize_nearest(-1.0, &levels), 0.0);
+        assert_eq!(quantize_nearest(2.0, &levels), 1.0);
+    }
+    
+    #[test]
+    fn test_screen_space_methods_preserve_average_brightness() {
+        let levels = [0.0, 1.0];
+        for method in [DitherMethod::Ordered, DitherMethod::BlueNoise, DitherMethod::FloydSteinberg] {
+            let mut frame = flat_frame(0.3, 64, 64);
+            method.ditherer().dither_frame(&mut frame, &levels, &DitherConfig::default(), Threading::Single);
+            
+            let samples: Vec<f64> = frame.iter().flatten().flatten().map(|s| s.brightness).collect();
+            assert!(samples.iter().all(|&b| b == 0.0 || b == 1.0), "{:?}", method);
+            let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+            assert!((mean - 0.3).abs() < 0.02, "{:?} mean {}", method, mean);
+        }
+    }
+    
+    #[test]
+    fn test_empty_pixels_untouched() {
+        let mut frame = flat_frame(0.7, 8, 8);
+        frame[3][3] = None;
+        for method in DitherMethod::ALL {
+            let mut copy = frame.clone();
+            method.ditherer().dither_frame(&mut copy, &[0.0, 0.5, 1.0], &DitherConfig::default(), Threading::Single);
+            assert!(copy[3][3].is_none());
+        }
+    }
+}
@@ -0,0 +1,170 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Ported from Dither3D by Rune Skovbo Johansen
+ * Original: https://github.com/runevision/Dither3D
+ */
+
+use super::bayer::BayerPatterns;
+use super::config::MAX_LEVEL;
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+// Texels along one dot spacing. Smaller texels have fewer dots that could
+// be nearest somewhere inside them, so fewer to check per lookup.
+const TEXELS_PER_DOT: usize = 16;
+// Cap on texels per side of one level
+const MAX_RESOLUTION: usize = 128;
+// The normalisation `DitherPattern::sample_pattern` uses
+const DEFAULT_DOT_SCALE: f64 = 2.4;
+// Slack on the distance bounds for rounding, far below any distance that matters
+const BOUND_SLACK: f64 = 1e-9;
+
+/// One fractal level of the lookup table: for each texel, the dots that
+/// can be the nearest one somewhere inside it.
+struct LevelTable {
+    resolution: usize, // Texels per side of one pattern tile
+    starts: Vec<usize>, // Where each texel's dots begin in `candidates`, plus the end of the last
+    candidates: Vec<u16>, // Dot indices, ascending within each texel
+}
+
+/// Precomputed dither pattern lookups, indexed by UV and dot count.
+/// 
+/// A pattern value depends on the distance to the nearest of the first
+/// `dot_count` dots. Each texel of the table lists, in order, only the
+/// dots that could be that nearest dot for some UV inside the texel and
+/// some dot count: a dot is left out once an earlier one is closer to
+/// every point of the texel than it is to any. A lookup measures the exact
+/// distance to just those dots, so it gives exactly what
+/// `DitherPattern::sample_pattern` does, without the search over every
+/// Bayer point.
+/// 
+/// Levels are built the first time they're sampled.
+pub struct DitherLut {
+    points: Vec<Vec<(f64, f64)>>,
+    levels: Vec<OnceLock<LevelTable>>,
+}
+
+impl DitherLut {
+    /// Creates the table for every level in `patterns`.
+    pub fn new(patterns: &BayerPatterns) -> Self {
+        Self {
+            points: (0..=MAX_LEVEL).map(|level| patterns.get_level(level).to_vec()).collect(),
+            levels: (0..=MAX_LEVEL).map(|_| OnceLock::new()).collect(),
+        }
+    }
+    
+    /// Samples the table at a given UV coordinate.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uv` - UV coordinates in world space; the pattern tiles every 1.0
+    /// * `level` - Fractal level (0-4, where 4 is finest)
+    /// * `dot_count` - Number of dots to use from the pattern (1 to pattern_size)
+    /// 
+    /// # Returns
+    /// 
+    /// A pattern value in [0.0, 1.0], the same as `DitherPattern::sample_pattern`
+    pub fn sample(&self, uv: (f64, f64), level: usize, dot_count: usize) -> f64 {
+        self.sample_scaled(uv, level, dot_count, DEFAULT_DOT_SCALE)
+    }
+    
+    /// Like `sample`, with the dot falloff in multiples of the dot radius
+    /// instead of the default 2.4.
+    pub fn sample_scaled(&self, uv: (f64, f64), level: usize, dot_count: usize, dot_scale: f64) -> f64 {
+        let level = level.min(MAX_LEVEL);
+        let points = &self.points[level];
+        let table = self.levels[level].get_or_init(|| build_level(points));
+        let dot_count = dot_count.min(points.len());
+        
+        if dot_count == 0 {
+            return 0.0;
+        }
+        
+        let res = table.resolution;
+        let tx = ((uv.0.rem_euclid(1.0) * res as f64) as usize).min(res - 1);
+        let ty = ((uv.1.rem_euclid(1.0) * res as f64) as usize).min(res - 1);
+        let texel = ty * res + tx;
+        
+        // Same distance and normalisation as DitherPattern::sample_pattern,
+        // over the dots that can be nearest in this texel
+        let mut min_dist = f64::INFINITY;
+        for &i in table.candidates[table.starts[texel]..table.starts[texel + 1]].iter().take_while(|&&i| (i as usize) < dot_count) {
+            min_dist = min_dist.min(wrapped_distance(uv, points[i as usize]));
+        }
+        let dot_radius = (0.5 / dot_count as f64 / PI).sqrt();
+        (1.0 - min_dist / (dot_radius * dot_scale)).clamp(0.0, 1.0)
+    }
+}
+
+// Distance from `uv` to `point` on the tiled pattern
+fn wrapped_distance(uv: (f64, f64), point: (f64, f64)) -> f64 {
+    let vec = (
+        (uv.0 - point.0 + 0.5).rem_euclid(1.0) - 0.5,
+        (uv.1 - point.1 + 0.5).rem_euclid(1.0) - 0.5,
+    );
+    (vec.0 * vec.0 + vec.1 * vec.1).sqrt()
+}
+
+fn build_level(points: &[(f64, f64)]) -> LevelTable {
+    let dots_per_side = (points.len() as f64).sqrt().ceil() as usize;
+    let res = (dots_per_side.max(1) * TEXELS_PER_DOT).min(MAX_RESOLUTION);
+    // Every point of a texel is within this of its centre
+    let half_diagonal = 0.5_f64.sqrt() / res as f64 + BOUND_SLACK;
+    let mut starts = Vec::with_capacity(res * res + 1);
+    let mut candidates = Vec::new();
+    
+    for ty in 0..res {
+        for tx in 0..res {
+            starts.push(candidates.len());
+            let centre = ((tx as f64 + 0.5) / res as f64, (ty as f64 + 0.5) / res as f64);
+            
+            // A dot can only be nearest if it can be closer than every
+            // earlier dot is at worst
+            let mut nearest_at_worst = f64::INFINITY;
+            for (i, &point) in points.iter().enumerate() {
+                let dist = wrapped_distance(centre, point);
+                if dist - half_diagonal <= nearest_at_worst {
+                    candidates.push(i as u16);
+                }
+                nearest_at_worst = nearest_at_worst.min(dist + half_diagonal);
+            }
+        }
+    }
+    starts.push(candidates.len());
+    
+    LevelTable { resolution: res, starts, candidates }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dither::DitherPattern;
+    
+    #[test]
+    fn test_lut_matches_direct_sampling() {
+        let pattern = DitherPattern::new();
+        let patterns = BayerPatterns::new();
+        let lut = DitherLut::new(&patterns);
+        
+        for level in 0..=MAX_LEVEL {
+            let max_dots = patterns.get_level(level).len();
+            let res = lut.levels[level].get_or_init(|| build_level(&lut.points[level])).resolution;
+            // Off-grid coordinates, including negative and beyond one tile,
+            // and texel corners
+            let mut uvs: Vec<(f64, f64)> = (0..20)
+                .flat_map(|i| (0..20).map(move |j| (i as f64 * 0.1731 - 1.3, j as f64 * 0.1593 + 0.7)))
+                .collect();
+            uvs.extend((0..=res).step_by(res / 8).map(|i| (i as f64 / res as f64, (res - i) as f64 / res as f64)));
+            
+            for dot_count in 0..=max_dots {
+                for &uv in &uvs {
+                    let expected = pattern.sample_pattern(uv, level, dot_count);
+                    assert_eq!(lut.sample(uv, level, dot_count), expected, "level {} dots {} at {:?}", level, dot_count, uv);
+                }
+            }
+        }
+    }
+}
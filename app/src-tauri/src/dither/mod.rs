@@ -11,7 +11,15 @@
  */
 
 pub mod bayer;
+pub mod blue_noise;
+pub mod config;
+pub mod diffusion;
+pub mod ditherer;
+pub mod lut;
+pub mod ordered;
 pub mod pattern;
 
+pub use config::{DitherConfig, UvMapping};
+pub use ditherer::{DitherMethod, Ditherer, Sample};
 pub use pattern::DitherPattern;
 
@@ -0,0 +1,72 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use super::config::DitherConfig;
+use super::ditherer::{quantize_with_threshold, Ditherer, Sample};
+use crate::parallel::{for_each_indexed, Threading};
+
+// Side length of the threshold matrix
+const SIZE: usize = 8;
+
+/// Classic ordered dithering with an 8x8 Bayer matrix tiled over the screen.
+/// 
+/// The pattern is fixed to the screen, not to surfaces, so it stays still
+/// while walls slide underneath it.
+pub struct OrderedBayer;
+
+impl OrderedBayer {
+    /// Threshold in [0.0, 1.0) for a screen position.
+    pub fn threshold(x: usize, y: usize) -> f64 {
+        (bayer_index(x % SIZE, y % SIZE, SIZE) as f64 + 0.5) / (SIZE * SIZE) as f64
+    }
+}
+
+impl Ditherer for OrderedBayer {
+    fn dither_frame(&self, rows: &mut [Vec<Option<Sample>>], levels: &[f64], _config: &DitherConfig, threading: Threading) {
+        for_each_indexed(rows, threading, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                if let Some(sample) = pixel {
+                    sample.brightness = quantize_with_threshold(sample.brightness, Self::threshold(x, y), levels);
+                }
+            }
+        });
+    }
+}
+
+// Rank of (x, y) in a size x size Bayer matrix, built by the usual recursion
+// M(2n) = [4M, 4M + 2; 4M + 3, 4M + 1]
+fn bayer_index(x: usize, y: usize, size: usize) -> usize {
+    if size == 1 {
+        return 0;
+    }
+    let half = size / 2;
+    let quadrant = match (x >= half, y >= half) {
+        (false, false) => 0,
+        (true, false) => 2,
+        (false, true) => 3,
+        (true, true) => 1,
+    };
+    4 * bayer_index(x % half, y % half, half) + quadrant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_matrix_is_a_permutation() {
+        let mut seen = [false; SIZE * SIZE];
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                seen[bayer_index(x, y, SIZE)] = true;
+            }
+        }
+        assert!(seen.iter().all(|&s| s));
+        
+        // The 2x2 case is the textbook [0 2; 3 1]
+        assert_eq!([bayer_index(0, 0, 2), bayer_index(1, 0, 2), bayer_index(0, 1, 2), bayer_index(1, 1, 2)], [0, 2, 3, 1]);
+    }
+}
@@ -8,6 +8,11 @@
  */
 
 use super::bayer::BayerPatterns;
+use super::config::{DitherConfig, MAX_LEVEL};
+use super::lut::DitherLut;
+use std::sync::OnceLock;
+
+static SHARED_PATTERN: OnceLock<DitherPattern> = OnceLock::new();
 
 /// Main dithering pattern structure that handles fractal dithering.
 /// 
@@ -17,20 +22,32 @@ use super::bayer::BayerPatterns;
 /// - The pattern is sampled based on world-space UV coordinates
 pub struct DitherPattern {
     patterns: BayerPatterns,
+    lut: DitherLut,
 }
 
 impl DitherPattern {
-    /// Creates a new DitherPattern with precomputed Bayer patterns.
+    /// Creates a new DitherPattern with precomputed Bayer patterns and
+    /// their lookup table.
     pub fn new() -> Self {
-        Self {
-            patterns: BayerPatterns::new(),
-        }
+        let patterns = BayerPatterns::new();
+        let lut = DitherLut::new(&patterns);
+        Self { patterns, lut }
+    }
+    
+    /// Returns a process-wide pattern, built on first use.
+    /// 
+    /// The pattern and its lookup table are immutable once built, so they
+    /// are built once and shared by every frame and every render thread.
+    pub fn shared() -> &'static DitherPattern {
+        SHARED_PATTERN.get_or_init(DitherPattern::new)
     }
     
     /// Samples the dither pattern at a given UV coordinate and brightness.
     /// 
     /// This finds the minimum distance to any Bayer point and converts it
     /// to a pattern value that can be compared against a brightness threshold.
+    /// It is the reference the lookup table is built from; rendering goes
+    /// through `dither`, which reads the table instead.
     /// 
     /// # Arguments
     /// 
@@ -91,15 +108,23 @@ impl DitherPattern {
     /// - level: The fractal level (0-3)
     /// - interpolation_factor: How much to blend with the next level (0.0-1.0)
     pub fn select_level(&self, normalized_dist: f64) -> (usize, f64) {
+        self.select_level_with(normalized_dist, &DitherConfig::default())
+    }
+    
+    /// Selects the fractal level using the level bias and finest level from
+    /// `config`.
+    pub fn select_level_with(&self, normalized_dist: f64, config: &DitherConfig) -> (usize, f64) {
         // Map distance to level: closer = finer pattern
         // normalized_dist: 0.0 (close) -> 1.0 (far)
-        // We want: close -> level 3 (8x8), far -> level 0 (1x1)
+        // We want: close -> finest level, far -> level 0 (1x1)
+        let max_level = config.max_level.min(MAX_LEVEL);
         
-        let level_float = (1.0 - normalized_dist) * 3.0;
+        let level_float = ((1.0 - normalized_dist) * max_level as f64 + config.level_bias)
+            .clamp(0.0, max_level as f64);
         let level = level_float.floor() as usize;
         let interp = level_float - level as f64;
         
-        (level.min(3), interp)
+        (level.min(max_level), interp)
     }
     
     /// Applies fractal dithering to a brightness value.
@@ -118,26 +143,23 @@ impl DitherPattern {
     /// 
     /// Dithered brightness value in [0.0, 1.0]
     pub fn dither(&self, normalized_dist: f64, uv: (f64, f64), brightness: f64) -> f64 {
+        self.dither_with(normalized_dist, uv, brightness, &DitherConfig::default())
+    }
+    
+    /// Applies fractal dithering with the level and dot settings from `config`.
+    /// 
+    /// Contrast and UV mapping are applied by the caller when it builds the
+    /// brightness and UV, so they work for every dithering method.
+    pub fn dither_with(&self, normalized_dist: f64, uv: (f64, f64), brightness: f64, config: &DitherConfig) -> f64 {
         // Select fractal level based on distance
-        let (level, interp) = self.select_level(normalized_dist);
-        
-        // Calculate dot count based on brightness
-        // Higher brightness = more dots visible
-        let max_dots = self.patterns.get_level(level).len();
-        let dot_count = (brightness * max_dots as f64).ceil() as usize;
-        let dot_count = dot_count.max(1).min(max_dots);
+        let (level, interp) = self.select_level_with(normalized_dist, config);
         
         // Sample pattern at current level
-        let pattern_value = self.sample_pattern(uv, level, dot_count);
+        let pattern_value = self.sample_level(uv, level, brightness, config.dot_scale);
         
         // If interpolation is needed, sample next level too
-        if interp > 0.001 && level < 3 {
-            let next_level = level + 1;
-            let next_max_dots = self.patterns.get_level(next_level).len();
-            let next_dot_count = (brightness * next_max_dots as f64).ceil() as usize;
-            let next_dot_count = next_dot_count.max(1).min(next_max_dots);
-            
-            let next_pattern_value = self.sample_pattern(uv, next_level, next_dot_count);
+        if interp > 0.001 && level < config.max_level.min(MAX_LEVEL) {
+            let next_pattern_value = self.sample_level(uv, level + 1, brightness, config.dot_scale);
             
             // Interpolate between levels
             return pattern_value * (1.0 - interp) + next_pattern_value * interp;
@@ -145,6 +167,16 @@ impl DitherPattern {
         
         pattern_value
     }
+    
+    fn sample_level(&self, uv: (f64, f64), level: usize, brightness: f64, dot_scale: f64) -> f64 {
+        // Calculate dot count based on brightness
+        // Higher brightness = more dots visible
+        let max_dots = self.patterns.get_level(level).len();
+        let dot_count = (brightness * max_dots as f64).ceil() as usize;
+        let dot_count = dot_count.max(1).min(max_dots);
+        
+        self.lut.sample_scaled(uv, level, dot_count, dot_scale)
+    }
 }
 
 impl Default for DitherPattern {
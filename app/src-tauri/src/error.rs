@@ -0,0 +1,54 @@
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// Why a command couldn't do what the frontend asked.
+/// 
+/// Commands return these instead of panicking, so a bad payload leaves the
+/// backend running. They reach the frontend as the rejected value of
+/// `invoke`, tagged with their kind, e.g.
+/// `{ "kind": "UnknownLevel", "level": 9 }`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum GameError {
+    BadState { message: String }, // A payload, or a session, the backend can't make sense of
+    InvalidDimensions { width: usize, height: usize }, // Frame size that can't be rendered
+    Io { path: String, message: String }, // Reading or writing a file failed
+    UnknownLevel { level: u8 }, // Levels run from 1 to 5
+}
+
+impl GameError {
+    pub fn bad_state(message: impl Into<String>) -> Self {
+        GameError::BadState { message: message.into() }
+    }
+    
+    pub fn io(path: &Path, error: std::io::Error) -> Self {
+        GameError::Io { path: path.display().to_string(), message: error.to_string() }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::BadState { message } => write!(f, "{}", message),
+            GameError::InvalidDimensions { width, height } => write!(f, "Can't render a {}x{} frame", width, height),
+            GameError::Io { path, message } => write!(f, "{}: {}", path, message),
+            GameError::UnknownLevel { level } => write!(f, "There is no level {}", level),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_errors_serialize_with_their_kind() {
+        let json = serde_json::to_value(GameError::UnknownLevel { level: 9 }).unwrap();
+        assert_eq!(json, serde_json::json!({ "kind": "UnknownLevel", "level": 9 }));
+        let json = serde_json::to_value(GameError::bad_state("No game session 3")).unwrap();
+        assert_eq!(json, serde_json::json!({ "kind": "BadState", "message": "No game session 3" }));
+    }
+}
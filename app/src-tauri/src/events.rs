@@ -0,0 +1,52 @@
+use crate::error::GameError;
+use crate::session::SessionId;
+use serde::Serialize;
+
+/// Something that happened in a game that the frontend should react to.
+/// 
+/// The simulation queues these as it runs, and after each command they're
+/// sent to the frontend as `game-event`s, e.g.
+/// `{ "session": 0, "type": "LevelCompleted", "level": 2, "time": 41.3, "record": true }`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    LevelStarted { level: u8 },
+    LevelCompleted { level: u8, time: f64, record: bool }, // `record` is true when `time` is the level's new best
+    RecordBroken { record: Record, time: f64 },
+    /// A pursuer reached the player, failing the level. Nothing raises it
+    /// yet: the game has no pursuers, so it's here for when they're added.
+    PlayerCaught,
+    /// The player picked up an item, named by `item`. Nothing raises it yet:
+    /// there are no items to pick up so far.
+    ItemPicked { item: String },
+    SaveFailed { error: GameError }, // Best times or a best run couldn't be written; the game carries on
+}
+
+/// Which best time a record replaced.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum Record {
+    Level(u8),
+    Total, // All five levels in one run
+}
+
+/// An event, and the session it happened in.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SessionEvent {
+    pub session: SessionId,
+    #[serde(flatten)]
+    pub event: GameEvent,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_events_serialize_flat_with_their_type() {
+        let message = SessionEvent { session: 3, event: GameEvent::RecordBroken { record: Record::Level(2), time: 12.5 } };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({ "session": 3, "type": "RecordBroken", "record": { "Level": 2 }, "time": 12.5 })
+        );
+    }
+}
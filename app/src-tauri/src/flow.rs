@@ -0,0 +1,306 @@
+use crate::events::GameEvent;
+use crate::hud::HudElement;
+use serde::{Deserialize, Serialize};
+
+// Levels in a run; finishing the last one ends the run
+const LAST_LEVEL: u8 = 5;
+
+/// Where the player is between the title screen and the end of a run.
+/// 
+/// The frontend only forwards keys; which one does what, and whether the
+/// game is running at all, is decided here.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameFlow {
+    #[default]
+    Title, // Before the run starts
+    Playing,
+    Paused(PauseMenu), // The simulation is stopped, so the level timer is too
+    LevelComplete, // Reached the exit of a level before the last
+    RunComplete, // Reached the exit of the last level
+    Failed, // Lost the level, e.g. caught by a pursuer
+}
+
+/// A key press, as far as the game flow is concerned.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FlowKey {
+    Confirm, // Space or Enter
+    Pause, // Escape
+    Restart, // Play the level again
+    Up, // Menu selection
+    Down,
+    FocusLost, // Not a key: the window went into the background
+    Map, // Open or close the automap
+}
+
+/// The menu shown while paused: which page, and which item on it is selected.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PauseMenu {
+    pub page: MenuPage,
+    pub selected: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MenuPage {
+    #[default]
+    Main,
+    Settings,
+    Hud, // Which HUD elements are shown, opened from the settings page
+}
+
+/// One line of the pause menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Resume,
+    RestartLevel,
+    Settings,
+    QuitToTitle,
+    Setting(Setting),
+    Hud,
+    Back,
+}
+
+/// A display setting the settings page can change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    MazeStyle,
+    GlyphRamp,
+    RenderMode,
+    DitherMethod,
+    Purist, // No automap
+    Hud(HudElement), // Shown or hidden
+}
+
+/// What the game has to do to follow a change of flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowAction {
+    Begin, // Start timing the level that's already been generated
+    NextLevel,
+    RetryLevel,
+    NewRun, // Back to level 1 with a fresh set of run times
+    ChangeSetting(Setting), // Move the setting on to its next value
+    ToggleMap,
+}
+
+impl MenuPage {
+    pub fn items(self) -> &'static [MenuItem] {
+        match self {
+            MenuPage::Main => &[MenuItem::Resume, MenuItem::RestartLevel, MenuItem::Settings, MenuItem::QuitToTitle],
+            MenuPage::Settings => &[
+                MenuItem::Setting(Setting::MazeStyle),
+                MenuItem::Setting(Setting::GlyphRamp),
+                MenuItem::Setting(Setting::RenderMode),
+                MenuItem::Setting(Setting::DitherMethod),
+                MenuItem::Setting(Setting::Purist),
+                MenuItem::Hud,
+                MenuItem::Back,
+            ],
+            MenuPage::Hud => &[
+                MenuItem::Setting(Setting::Hud(HudElement::Level)),
+                MenuItem::Setting(Setting::Hud(HudElement::Timer)),
+                MenuItem::Setting(Setting::Hud(HudElement::Best)),
+                MenuItem::Setting(Setting::Hud(HudElement::Compass)),
+                MenuItem::Setting(Setting::Hud(HudElement::Minimap)),
+                MenuItem::Back,
+            ],
+        }
+    }
+}
+
+impl PauseMenu {
+    pub fn item(self) -> MenuItem {
+        self.page.items()[self.selected]
+    }
+    
+    // The page this one was opened from, with the item that opened it
+    // selected, as it's left when coming back to it
+    fn back(self) -> Self {
+        let (page, opener) = match self.page {
+            MenuPage::Main | MenuPage::Settings => (MenuPage::Main, MenuItem::Settings),
+            MenuPage::Hud => (MenuPage::Settings, MenuItem::Hud),
+        };
+        let selected = page.items().iter().position(|&item| item == opener).unwrap_or(0);
+        PauseMenu { page, selected }
+    }
+    
+    // Moves the selection by `step`, wrapping round at either end
+    fn moved(self, step: isize) -> Self {
+        let count = self.page.items().len() as isize;
+        PauseMenu { selected: (self.selected as isize + step).rem_euclid(count) as usize, ..self }
+    }
+    
+    // What choosing the selected item does
+    fn choose(self) -> (GameFlow, Option<FlowAction>) {
+        match self.item() {
+            MenuItem::Resume => (GameFlow::Playing, None),
+            MenuItem::RestartLevel => (GameFlow::Playing, Some(FlowAction::RetryLevel)),
+            MenuItem::Settings => (GameFlow::Paused(PauseMenu { page: MenuPage::Settings, selected: 0 }), None),
+            MenuItem::QuitToTitle => (GameFlow::Title, Some(FlowAction::NewRun)),
+            MenuItem::Setting(setting) => (GameFlow::Paused(self), Some(FlowAction::ChangeSetting(setting))),
+            MenuItem::Hud => (GameFlow::Paused(PauseMenu { page: MenuPage::Hud, selected: 0 }), None),
+            MenuItem::Back => (GameFlow::Paused(self.back()), None),
+        }
+    }
+}
+
+impl MenuItem {
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuItem::Resume => "Resume",
+            MenuItem::RestartLevel => "Restart Level",
+            MenuItem::Settings => "Settings",
+            MenuItem::QuitToTitle => "Quit to Title",
+            MenuItem::Setting(Setting::MazeStyle) => "Maze",
+            MenuItem::Setting(Setting::GlyphRamp) => "Glyphs",
+            MenuItem::Setting(Setting::RenderMode) => "Rendering",
+            MenuItem::Setting(Setting::DitherMethod) => "Dithering",
+            MenuItem::Setting(Setting::Purist) => "Purist Mode",
+            MenuItem::Setting(Setting::Hud(HudElement::Level)) => "Level",
+            MenuItem::Setting(Setting::Hud(HudElement::Timer)) => "Timer",
+            MenuItem::Setting(Setting::Hud(HudElement::Best)) => "Best Time",
+            MenuItem::Setting(Setting::Hud(HudElement::Compass)) => "Compass",
+            MenuItem::Setting(Setting::Hud(HudElement::Minimap)) => "Minimap",
+            MenuItem::Hud => "HUD",
+            MenuItem::Back => "Back",
+        }
+    }
+}
+
+impl GameFlow {
+    /// The flow after `key` is pressed, and what the game has to do about
+    /// it. Keys that mean nothing in the current state change nothing.
+    pub fn press(self, key: FlowKey) -> (GameFlow, Option<FlowAction>) {
+        use FlowKey::*;
+        use GameFlow::*;
+        match (self, key) {
+            (Title, Confirm) => (Playing, Some(FlowAction::Begin)),
+            (Playing, Pause | FocusLost) => (Paused(PauseMenu::default()), None),
+            (Playing, Map) => (Playing, Some(FlowAction::ToggleMap)),
+            (Paused(menu), Confirm) => menu.choose(),
+            (Paused(menu), Up) => (Paused(menu.moved(-1)), None),
+            (Paused(menu), Down) => (Paused(menu.moved(1)), None),
+            // Escape backs out of a page, and resumes from the main one
+            (Paused(menu), Pause) if menu.page != MenuPage::Main => (Paused(menu.back()), None),
+            (Paused(_), Pause) => (Playing, None),
+            (Playing | Paused(_) | LevelComplete | RunComplete | Failed, Restart) => (Playing, Some(FlowAction::RetryLevel)),
+            (Failed, Confirm) => (Playing, Some(FlowAction::RetryLevel)),
+            (Failed, Pause) => (Title, Some(FlowAction::NewRun)),
+            (LevelComplete, Confirm) => (Playing, Some(FlowAction::NextLevel)),
+            (RunComplete, Confirm) => (Title, Some(FlowAction::NewRun)),
+            (state, _) => (state, None),
+        }
+    }
+    
+    /// The flow after something happened in the game.
+    pub fn on_event(self, event: &GameEvent) -> GameFlow {
+        match (self, event) {
+            (GameFlow::Playing, GameEvent::LevelCompleted { level, .. }) if *level >= LAST_LEVEL => GameFlow::RunComplete,
+            (GameFlow::Playing, GameEvent::LevelCompleted { .. }) => GameFlow::LevelComplete,
+            (GameFlow::Playing, GameEvent::PlayerCaught) => GameFlow::Failed,
+            (state, _) => state,
+        }
+    }
+    
+    /// True while the simulation should run.
+    pub fn is_running(self) -> bool {
+        self == GameFlow::Playing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn completed(level: u8) -> GameEvent {
+        GameEvent::LevelCompleted { level, time: 10.0, record: false }
+    }
+    
+    #[test]
+    fn test_a_run_from_title_to_title() {
+        let (flow, action) = GameFlow::Title.press(FlowKey::Confirm);
+        assert_eq!((flow, action), (GameFlow::Playing, Some(FlowAction::Begin)));
+        
+        let flow = flow.on_event(&completed(1));
+        assert_eq!(flow, GameFlow::LevelComplete);
+        let (flow, action) = flow.press(FlowKey::Confirm);
+        assert_eq!((flow, action), (GameFlow::Playing, Some(FlowAction::NextLevel)));
+        
+        let flow = flow.on_event(&completed(5));
+        assert_eq!(flow, GameFlow::RunComplete);
+        assert_eq!(flow.press(FlowKey::Confirm), (GameFlow::Title, Some(FlowAction::NewRun)));
+    }
+    
+    #[test]
+    fn test_a_failed_level_is_retried_or_given_up() {
+        let failed = GameFlow::Playing.on_event(&GameEvent::PlayerCaught);
+        assert_eq!(failed, GameFlow::Failed);
+        assert!(!failed.is_running());
+        assert_eq!(failed.press(FlowKey::Confirm), (GameFlow::Playing, Some(FlowAction::RetryLevel)));
+        assert_eq!(failed.press(FlowKey::Restart), (GameFlow::Playing, Some(FlowAction::RetryLevel)));
+        assert_eq!(failed.press(FlowKey::Pause), (GameFlow::Title, Some(FlowAction::NewRun)));
+        assert_eq!(failed.press(FlowKey::Map), (GameFlow::Failed, None));
+        // Only a level being played can be lost
+        assert_eq!(GameFlow::LevelComplete.on_event(&GameEvent::PlayerCaught), GameFlow::LevelComplete);
+    }
+    
+    // Presses `keys` in turn, keeping the last action
+    fn press_all(mut flow: GameFlow, keys: &[FlowKey]) -> (GameFlow, Option<FlowAction>) {
+        let mut action = None;
+        for &key in keys {
+            (flow, action) = flow.press(key);
+        }
+        (flow, action)
+    }
+    
+    #[test]
+    fn test_pause_and_restart() {
+        let (paused, action) = GameFlow::Playing.press(FlowKey::Pause);
+        assert_eq!((paused, action), (GameFlow::Paused(PauseMenu::default()), None));
+        assert!(!paused.is_running());
+        // Completing a level can't happen while paused, and wouldn't count
+        assert_eq!(paused.on_event(&completed(2)), paused);
+        assert_eq!(paused.press(FlowKey::Pause), (GameFlow::Playing, None));
+        assert_eq!(paused.press(FlowKey::Confirm), (GameFlow::Playing, None));
+        assert_eq!(paused.press(FlowKey::Restart), (GameFlow::Playing, Some(FlowAction::RetryLevel)));
+        assert_eq!(GameFlow::LevelComplete.press(FlowKey::Restart), (GameFlow::Playing, Some(FlowAction::RetryLevel)));
+    }
+    
+    #[test]
+    fn test_keys_that_mean_nothing_change_nothing() {
+        // A held or repeated space can't skip past a level or the title
+        assert_eq!(GameFlow::Playing.press(FlowKey::Confirm), (GameFlow::Playing, None));
+        assert_eq!(GameFlow::Title.press(FlowKey::Pause), (GameFlow::Title, None));
+        assert_eq!(GameFlow::Title.press(FlowKey::Restart), (GameFlow::Title, None));
+        assert_eq!(GameFlow::LevelComplete.press(FlowKey::Map), (GameFlow::LevelComplete, None));
+        assert_eq!(GameFlow::LevelComplete.press(FlowKey::Pause), (GameFlow::LevelComplete, None));
+        assert_eq!(GameFlow::Title.on_event(&completed(1)), GameFlow::Title);
+        // Losing focus pauses a level being played, and never unpauses one
+        let paused = GameFlow::Playing.press(FlowKey::FocusLost).0;
+        assert_eq!(paused.press(FlowKey::FocusLost), (paused, None));
+        assert_eq!(GameFlow::LevelComplete.press(FlowKey::FocusLost), (GameFlow::LevelComplete, None));
+    }
+    
+    #[test]
+    fn test_pause_menu() {
+        let paused = GameFlow::Playing.press(FlowKey::Pause).0;
+        let down = FlowKey::Down;
+        assert_eq!(press_all(paused, &[down, FlowKey::Confirm]), (GameFlow::Playing, Some(FlowAction::RetryLevel)));
+        assert_eq!(press_all(paused, &[FlowKey::Up, FlowKey::Confirm]), (GameFlow::Title, Some(FlowAction::NewRun)));
+        
+        // Settings change in place, and Back or Escape return to the main page at Settings
+        let (settings, _) = press_all(paused, &[down, down, FlowKey::Confirm]);
+        assert_eq!(settings, GameFlow::Paused(PauseMenu { page: MenuPage::Settings, selected: 0 }));
+        let (flow, action) = press_all(settings, &[down, FlowKey::Confirm]);
+        assert_eq!(action, Some(FlowAction::ChangeSetting(Setting::GlyphRamp)));
+        assert_eq!(flow, GameFlow::Paused(PauseMenu { page: MenuPage::Settings, selected: 1 }));
+        let main = GameFlow::Paused(PauseMenu { page: MenuPage::Main, selected: 2 });
+        assert_eq!(settings.press(FlowKey::Pause), (main, None));
+        assert_eq!(press_all(settings, &[FlowKey::Up, FlowKey::Confirm]), (main, None));
+        
+        // The HUD page opens from settings, and backs out to it
+        let (hud, _) = press_all(settings, &[FlowKey::Up, FlowKey::Up, FlowKey::Confirm]);
+        assert_eq!(hud, GameFlow::Paused(PauseMenu { page: MenuPage::Hud, selected: 0 }));
+        let (_, action) = press_all(hud, &[down, FlowKey::Confirm]);
+        assert_eq!(action, Some(FlowAction::ChangeSetting(Setting::Hud(HudElement::Timer))));
+        assert_eq!(hud.press(FlowKey::Pause), (GameFlow::Paused(PauseMenu { page: MenuPage::Settings, selected: 5 }), None));
+    }
+}
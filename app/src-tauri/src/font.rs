@@ -0,0 +1,206 @@
+use crate::overlay::display_width;
+
+/// Block-letter fonts for banners, biggest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Big, // Six rows of block letters with box-drawing shadows
+    Small, // Three rows of half blocks
+    Plain, // The text as it is
+}
+
+// Columns between small letters
+const SMALL_GAP: usize = 1;
+
+// Each big letter's six rows, shadow included, so letters are simply set
+// side by side
+const BIG: &[(char, [&str; 6])] = &[
+    ('A', [" █████╗ ", "██╔══██╗", "███████║", "██╔══██║", "██║  ██║", "╚═╝  ╚═╝"]),
+    ('B', ["██████╗ ", "██╔══██╗", "██████╔╝", "██╔══██╗", "██████╔╝", "╚═════╝ "]),
+    ('C', [" ██████╗", "██╔════╝", "██║     ", "██║     ", "╚██████╗", " ╚═════╝"]),
+    ('D', ["██████╗ ", "██╔══██╗", "██║  ██║", "██║  ██║", "██████╔╝", "╚═════╝ "]),
+    ('E', ["███████╗", "██╔════╝", "█████╗  ", "██╔══╝  ", "███████╗", "╚══════╝"]),
+    ('F', ["███████╗", "██╔════╝", "█████╗  ", "██╔══╝  ", "██║     ", "╚═╝     "]),
+    ('G', [" ██████╗ ", "██╔════╝ ", "██║  ███╗", "██║   ██║", "╚██████╔╝", " ╚═════╝ "]),
+    ('H', ["██╗  ██╗", "██║  ██║", "███████║", "██╔══██║", "██║  ██║", "╚═╝  ╚═╝"]),
+    ('I', ["██╗", "██║", "██║", "██║", "██║", "╚═╝"]),
+    ('J', ["     ██╗", "     ██║", "     ██║", "██   ██║", "╚█████╔╝", " ╚════╝ "]),
+    ('K', ["██╗  ██╗", "██║ ██╔╝", "█████╔╝ ", "██╔═██╗ ", "██║  ██╗", "╚═╝  ╚═╝"]),
+    ('L', ["██╗     ", "██║     ", "██║     ", "██║     ", "███████╗", "╚══════╝"]),
+    ('M', ["███╗   ███╗", "████╗ ████║", "██╔████╔██║", "██║╚██╔╝██║", "██║ ╚═╝ ██║", "╚═╝     ╚═╝"]),
+    ('N', ["███╗   ██╗", "████╗  ██║", "██╔██╗ ██║", "██║╚██╗██║", "██║ ╚████║", "╚═╝  ╚═══╝"]),
+    ('O', [" ██████╗ ", "██╔═══██╗", "██║   ██║", "██║   ██║", "╚██████╔╝", " ╚═════╝ "]),
+    ('P', ["██████╗ ", "██╔══██╗", "██████╔╝", "██╔═══╝ ", "██║     ", "╚═╝     "]),
+    ('Q', [" ██████╗ ", "██╔═══██╗", "██║   ██║", "██║▄▄ ██║", "╚██████╔╝", " ╚══▀▀═╝ "]),
+    ('R', ["██████╗ ", "██╔══██╗", "██████╔╝", "██╔══██╗", "██║  ██║", "╚═╝  ╚═╝"]),
+    ('S', ["███████╗", "██╔════╝", "███████╗", "╚════██║", "███████║", "╚══════╝"]),
+    ('T', ["████████╗", "╚══██╔══╝", "   ██║   ", "   ██║   ", "   ██║   ", "   ╚═╝   "]),
+    ('U', ["██╗   ██╗", "██║   ██║", "██║   ██║", "██║   ██║", "╚██████╔╝", " ╚═════╝ "]),
+    ('V', ["██╗   ██╗", "██║   ██║", "██║   ██║", "╚██╗ ██╔╝", " ╚████╔╝ ", "  ╚═══╝  "]),
+    ('W', ["██╗    ██╗", "██║    ██║", "██║ █╗ ██║", "██║███╗██║", "╚███╔███╔╝", " ╚══╝╚══╝ "]),
+    ('X', ["██╗  ██╗", "╚██╗██╔╝", " ╚███╔╝ ", " ██╔██╗ ", "██╔╝ ██╗", "╚═╝  ╚═╝"]),
+    ('Y', ["██╗   ██╗", "╚██╗ ██╔╝", " ╚████╔╝ ", "  ╚██╔╝  ", "   ██║   ", "   ╚═╝   "]),
+    ('Z', ["███████╗", "╚══███╔╝", "  ███╔╝ ", " ███╔╝  ", "███████╗", "╚══════╝"]),
+    ('0', [" ██████╗ ", "██╔═████╗", "██║██╔██║", "████╔╝██║", "╚██████╔╝", " ╚═════╝ "]),
+    ('1', [" ██╗", "███║", "╚██║", " ██║", " ██║", " ╚═╝"]),
+    ('2', ["██████╗ ", "╚════██╗", " █████╔╝", "██╔═══╝ ", "███████╗", "╚══════╝"]),
+    ('3', ["██████╗ ", "╚════██╗", " █████╔╝", " ╚═══██╗", "██████╔╝", "╚═════╝ "]),
+    ('4', ["██╗  ██╗", "██║  ██║", "███████║", "╚════██║", "     ██║", "     ╚═╝"]),
+    ('5', ["███████╗", "██╔════╝", "███████╗", "╚════██║", "███████║", "╚══════╝"]),
+    ('6', [" ██████╗ ", "██╔════╝ ", "███████╗ ", "██╔═══██╗", "╚██████╔╝", " ╚═════╝ "]),
+    ('7', ["███████╗", "╚════██║", "    ██╔╝", "   ██╔╝ ", "   ██║  ", "   ╚═╝  "]),
+    ('8', [" █████╗ ", "██╔══██╗", "╚█████╔╝", "██╔══██╗", "╚█████╔╝", " ╚════╝ "]),
+    ('9', [" █████╗ ", "██╔══██╗", "╚██████║", " ╚═══██║", " █████╔╝", " ╚════╝ "]),
+    ('!', ["██╗", "██║", "██║", "╚═╝", "██╗", "╚═╝"]),
+    ('-', ["      ", "      ", "█████╗", "╚════╝", "      ", "      "]),
+    (':', ["   ", "██╗", "╚═╝", "██╗", "╚═╝", "   "]),
+    ('.', ["   ", "   ", "   ", "   ", "██╗", "╚═╝"]),
+    (',', ["   ", "   ", "   ", "██╗", "▄█╝", "╚╝ "]),
+    ('\'', ["██╗", "██║", "╚═╝", "   ", "   ", "   "]),
+    ('?', ["██████╗ ", "╚════██╗", "  ▄███╔╝", "  ▀▀══╝ ", "  ██╗   ", "  ╚═╝   "]),
+    ('/', ["    ██╗", "   ██╔╝", "  ██╔╝ ", " ██╔╝  ", "██╔╝   ", "╚═╝    "]),
+    ('+', ["       ", "  ██╗  ", "██████╗", "╚═██╔═╝", "  ╚═╝  ", "       "]),
+    (' ', ["   ", "   ", "   ", "   ", "   ", "   "]),
+];
+
+// Each small letter as five rows of pixels, `#` set, drawn two rows of
+// pixels to a character with half blocks
+const SMALL: &[(char, [&str; 5])] = &[
+    ('A', [".#.", "#.#", "###", "#.#", "#.#"]),
+    ('B', ["##.", "#.#", "##.", "#.#", "##."]),
+    ('C', [".##", "#..", "#..", "#..", ".##"]),
+    ('D', ["##.", "#.#", "#.#", "#.#", "##."]),
+    ('E', ["###", "#..", "##.", "#..", "###"]),
+    ('F', ["###", "#..", "##.", "#..", "#.."]),
+    ('G', [".##", "#..", "#.#", "#.#", ".##"]),
+    ('H', ["#.#", "#.#", "###", "#.#", "#.#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..#", "..#", "..#", "#.#", ".#."]),
+    ('K', ["#.#", "#.#", "##.", "#.#", "#.#"]),
+    ('L', ["#..", "#..", "#..", "#..", "###"]),
+    ('M', ["#...#", "##.##", "#.#.#", "#...#", "#...#"]),
+    ('N', ["#..#", "##.#", "#.##", "#..#", "#..#"]),
+    ('O', [".#.", "#.#", "#.#", "#.#", ".#."]),
+    ('P', ["##.", "#.#", "##.", "#..", "#.."]),
+    ('Q', [".#.", "#.#", "#.#", "##.", ".##"]),
+    ('R', ["##.", "#.#", "##.", "#.#", "#.#"]),
+    ('S', [".##", "#..", ".#.", "..#", "##."]),
+    ('T', ["###", ".#.", ".#.", ".#.", ".#."]),
+    ('U', ["#.#", "#.#", "#.#", "#.#", "###"]),
+    ('V', ["#.#", "#.#", "#.#", "#.#", ".#."]),
+    ('W', ["#...#", "#...#", "#.#.#", "##.##", "#...#"]),
+    ('X', ["#.#", "#.#", ".#.", "#.#", "#.#"]),
+    ('Y', ["#.#", "#.#", ".#.", ".#.", ".#."]),
+    ('Z', ["###", "..#", ".#.", "#..", "###"]),
+    ('0', ["###", "#.#", "#.#", "#.#", "###"]),
+    ('1', [".#.", "##.", ".#.", ".#.", "###"]),
+    ('2', ["##.", "..#", ".#.", "#..", "###"]),
+    ('3', ["##.", "..#", ".#.", "..#", "##."]),
+    ('4', ["#.#", "#.#", "###", "..#", "..#"]),
+    ('5', ["###", "#..", "##.", "..#", "##."]),
+    ('6', [".##", "#..", "###", "#.#", "###"]),
+    ('7', ["###", "..#", ".#.", ".#.", ".#."]),
+    ('8', ["###", "#.#", "###", "#.#", "###"]),
+    ('9', ["###", "#.#", "###", "..#", "##."]),
+    ('!', ["#", "#", "#", ".", "#"]),
+    ('-', ["...", "...", "###", "...", "..."]),
+    (':', [".", "#", ".", "#", "."]),
+    ('.', [".", ".", ".", ".", "#"]),
+    (',', ["..", "..", "..", ".#", "#."]),
+    ('\'', ["#", "#", ".", ".", "."]),
+    ('?', ["##.", "..#", ".#.", "...", ".#."]),
+    ('/', ["..#", "..#", ".#.", "#..", "#.."]),
+    ('+', ["...", ".#.", "###", ".#.", "..."]),
+    (' ', ["..", "..", "..", "..", ".."]),
+];
+
+impl Font {
+    /// Every font, biggest first.
+    pub const ALL: [Font; 3] = [Font::Big, Font::Small, Font::Plain];
+    
+    /// `text` set in this font, one string per row, or `None` if it has a
+    /// character the font doesn't. Letters are set in capitals.
+    pub fn render(self, text: &str) -> Option<Vec<String>> {
+        match self {
+            Font::Big => {
+                let glyphs = text.chars().map(big_glyph).collect::<Option<Vec<_>>>()?;
+                Some((0..6).map(|row| glyphs.iter().map(|glyph| glyph[row]).collect()).collect())
+            }
+            Font::Small => {
+                let glyphs = text.chars().map(small_glyph).collect::<Option<Vec<_>>>()?;
+                let gap = ".".repeat(SMALL_GAP);
+                let pixels: Vec<String> = (0..5).map(|row| glyphs.iter().map(|glyph| glyph[row]).collect::<Vec<_>>().join(&gap)).collect();
+                Some(pixels.chunks(2).map(|rows| half_blocks(&rows[0], rows.get(1).map(String::as_str))).collect())
+            }
+            Font::Plain => Some(vec![text.to_string()]),
+        }
+    }
+}
+
+/// `text` as a banner no wider than `width` columns: in `largest` if it
+/// fits, or else the biggest smaller font it fits in, down to plain text.
+pub fn banner(text: &str, width: usize, largest: Font) -> Vec<String> {
+    Font::ALL
+        .iter()
+        .skip_while(|&&font| font != largest)
+        .filter_map(|font| font.render(text))
+        .find(|lines| lines.iter().all(|line| display_width(line) <= width))
+        .unwrap_or_else(|| vec![text.to_string()])
+}
+
+fn big_glyph(ch: char) -> Option<&'static [&'static str; 6]> {
+    let ch = ch.to_ascii_uppercase();
+    BIG.iter().find(|(glyph, _)| *glyph == ch).map(|(_, rows)| rows)
+}
+
+fn small_glyph(ch: char) -> Option<&'static [&'static str; 5]> {
+    let ch = ch.to_ascii_uppercase();
+    SMALL.iter().find(|(glyph, _)| *glyph == ch).map(|(_, rows)| rows)
+}
+
+// Two rows of pixels as one row of half blocks; a missing bottom row is blank
+fn half_blocks(top: &str, bottom: Option<&str>) -> String {
+    let bottom = bottom.unwrap_or("");
+    top.chars()
+        .zip(bottom.chars().chain(std::iter::repeat('.')))
+        .map(|pair| match pair {
+            ('#', '#') => '█',
+            ('#', _) => '▀',
+            (_, '#') => '▄',
+            _ => ' ',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_big_font_spells_level_complete() {
+        let lines = Font::Big.render("Level Complete").unwrap();
+        assert_eq!(lines[0], "██╗     ███████╗██╗   ██╗███████╗██╗         ██████╗ ██████╗ ███╗   ███╗██████╗ ██╗     ███████╗████████╗███████╗");
+        assert_eq!(lines[5], "╚══════╝╚══════╝  ╚═══╝  ╚══════╝╚══════╝    ╚═════╝ ╚═════╝ ╚═╝     ╚═╝╚═╝     ╚══════╝╚══════╝   ╚═╝   ╚══════╝");
+        // Every row of a letter is as wide as the rest
+        for (ch, rows) in BIG {
+            assert!(rows.iter().all(|row| display_width(row) == display_width(rows[0])), "{:?}", ch);
+        }
+    }
+    
+    #[test]
+    fn test_small_font_draws_pixels_in_half_blocks() {
+        assert_eq!(Font::Small.render("LI").unwrap(), vec!["█   ▀█▀", "█    █ ", "▀▀▀ ▀▀▀"]);
+        assert_eq!(Font::Small.render("é"), None);
+    }
+    
+    #[test]
+    fn test_banner_falls_back_to_smaller_fonts() {
+        assert_eq!(banner("PAUSED", 80, Font::Big).len(), 6);
+        assert_eq!(banner("PAUSED", 30, Font::Big).len(), 3);
+        assert_eq!(banner("PAUSED", 10, Font::Big), vec!["PAUSED"]);
+        assert_eq!(banner("PAUSED", 80, Font::Small).len(), 3);
+        assert_eq!(banner("LEVEL 3?", 80, Font::Big).len(), 6);
+        assert_eq!(banner("1/2, +3 'OK'", 80, Font::Small).len(), 3);
+        // Characters the block fonts don't have are left as they are
+        assert_eq!(banner("CAFÉ", 80, Font::Big), vec!["CAFÉ"]);
+    }
+}
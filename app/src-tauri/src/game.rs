@@ -1,8 +1,40 @@
-use crate::maze::Maze;
+use crate::clock::Clock;
+use crate::dither::{DitherConfig, DitherMethod};
+use crate::error::GameError;
+use crate::events::{GameEvent, Record};
+use crate::font::{banner, Font};
+use crate::ghost::Ghost;
+use crate::glyphs::GlyphRamp;
+use crate::hud::Hud;
+use crate::maze::{random_seed, Maze, WallMaterial, FULL_WALL_HEIGHT};
+use crate::overlay::{Anchor, Layer, Overlay};
+use crate::parallel::Threading;
 use crate::raycast::cast_ray;
+use crate::renderer::{Camera, RenderMode, RenderOptions, Renderer};
+use crate::replay::{Replay, TickInput};
+use crate::thin_maze::ThinMaze;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// Simulation rate, in ticks per second
+pub const TICK_RATE: f64 = 120.0;
+// Seconds of game time per tick
+pub const TICK: f64 = 1.0 / TICK_RATE;
+// Longest frame that is simulated in full; anything longer is cut short
+pub const MAX_FRAME_TIME: f64 = 0.1;
+// Units per second (0.03 per frame at 60fps, slower than original 0.05)
+const MOVE_SPEED: f64 = 1.8;
+// Radians per second (0.06 per frame at 60fps, slower than original 0.10)
+const TURN_SPEED: f64 = 3.6;
+// Radians per unit of `mouse_delta_x` (what the old per-frame scaling gave at 60fps)
+const MOUSE_SENSITIVITY: f64 = TURN_SPEED * 2.0 / 60.0;
+// Shade of the glyph the ghost is drawn with, and its braille dots (a sparse checker)
+const GHOST_BRIGHTNESS: f64 = 0.5;
+const GHOST_BRAILLE: char = '⢕';
+// Rays cast across the view to find the map cells the player can see
+const EXPLORE_RAYS: usize = 64;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameState {
@@ -10,11 +42,31 @@ pub struct GameState {
     pub player_y: f64,
     pub player_angle: f64,
     pub maze: MazeData,
+    #[serde(skip)]
+    built_maze: OnceLock<Maze>, // `maze` built into a `Maze` once, for collisions and raycasting
     pub exit_x: f64,
     pub exit_y: f64,
     pub has_won: bool,
     pub current_level: u8, // 1-5
-    pub level_start_time: f64, // Time when current level started (seconds since epoch)
+    #[serde(default)]
+    pub seed: u64, // Seed the level's maze was generated from
+    pub level_start_time: f64, // Clock reading when the current level started; only times the start banner
+    #[serde(default)]
+    pub level_elapsed: f64, // Play time on the current level, summed from simulation ticks
+    #[serde(default)]
+    pub tick_accumulator: f64, // Frame time not yet simulated, always less than one tick
+    #[serde(default)]
+    pub previous_pose: Option<(f64, f64, f64)>, // Player x, y and angle before the last tick, for interpolation
+    #[serde(default)]
+    pub pending_mouse: f64, // Mouse movement not yet turned by a tick
+    #[serde(default)]
+    pub replay: Replay, // Every tick played on this level so far
+    #[serde(default)]
+    pub is_replay: bool, // Played back from a replay, so best times are never saved
+    #[serde(default)]
+    pub ghost: Option<Ghost>, // Best run on this maze, raced tick for tick
+    #[serde(skip)]
+    pub events: Vec<GameEvent>, // Happened since they were last taken, oldest first
     pub level_completion_time: Option<f64>, // Time for current level (seconds elapsed)
     pub total_time: f64, // Cumulative time across all levels
     pub run_times: Vec<Option<f64>>, // Actual completion times for each level in this run (5 elements)
@@ -22,6 +74,32 @@ pub struct GameState {
     pub best_total_time: Option<f64>, // Best time for all 5 levels combined
     pub new_record_level: Option<u8>, // Level where new record was set (1-5, or None)
     pub new_record_total: bool, // True if new total record was set
+    #[serde(default)]
+    pub maze_style: MazeStyle,
+    #[serde(default)]
+    pub thin_maze: Option<ThinMaze>, // Walkable layout in ThinWalls style; `maze` then only holds its block expansion for the map
+    #[serde(default)]
+    pub glyph_ramp: GlyphRamp, // Characters walls and floor are drawn with
+    #[serde(default)]
+    pub render_mode: RenderMode,
+    #[serde(default)]
+    pub dither_method: DitherMethod,
+    #[serde(default)]
+    pub dither_config: DitherConfig,
+    #[serde(default)]
+    pub hud: Hud, // Which HUD elements are drawn while playing
+    #[serde(default)]
+    pub seen: Vec<Vec<bool>>, // Map cells the player has seen, indexed like `maze.cells`
+    #[serde(default)]
+    pub purist: bool, // The automap can't be opened
+}
+
+/// How maze walls are laid out.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum MazeStyle {
+    #[default]
+    Blocks, // Walls fill whole grid cells
+    ThinWalls, // Walls are thin segments between cells
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -29,6 +107,10 @@ pub struct MazeData {
     pub width: usize,
     pub height: usize,
     pub cells: Vec<Vec<bool>>,
+    #[serde(default)]
+    pub heights: Vec<Vec<f64>>,
+    #[serde(default)]
+    pub materials: Vec<Vec<WallMaterial>>,
     pub start: (usize, usize),
     pub exit: (usize, usize),
 }
@@ -39,6 +121,8 @@ impl From<&Maze> for MazeData {
             width: maze.width,
             height: maze.height,
             cells: maze.cells.clone(),
+            heights: maze.heights.clone(),
+            materials: maze.materials.clone(),
             start: maze.start,
             exit: maze.exit,
         }
@@ -47,17 +131,11 @@ impl From<&Maze> for MazeData {
 
 impl From<MazeData> for Maze {
     fn from(data: MazeData) -> Self {
-        Maze {
-            width: data.width,
-            height: data.height,
-            cells: data.cells,
-            start: data.start,
-            exit: data.exit,
-        }
+        Maze::from_layout(data.cells, data.heights, data.materials, data.start, data.exit)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
 pub struct PlayerInput {
     pub forward: bool,
     pub backward: bool,
@@ -70,12 +148,14 @@ pub struct PlayerInput {
 }
 
 impl GameState {
-    pub fn new() -> Self {
-        let (best_times, best_total_time) = Self::load_best_times();
-        Self::new_level(1, vec![None; 5], best_times, best_total_time, 0.0)
+    /// Level 1 of a new run, with the best times kept in `save_dir` if
+    /// there is one.
+    pub fn new(save_dir: Option<&Path>, clock: &dyn Clock) -> Self {
+        let (best_times, best_total_time) = save_dir.map_or((vec![None; 5], None), Self::load_best_times);
+        Self::new_level(1, vec![None; 5], best_times, best_total_time, 0.0, MazeStyle::Blocks, clock)
     }
     
-    pub fn next_level(&self) -> Self {
+    pub fn next_level(&self, clock: &dyn Clock) -> Self {
         if self.current_level < 5 {
             // Store the current level's completion time in run_times
             let mut new_run_times = self.run_times.clone();
@@ -90,35 +170,135 @@ impl GameState {
                 self.best_times.clone(),
                 self.best_total_time,
                 self.total_time,
+                self.maze_style,
+                clock,
             );
             // Reset record flags when moving to next level
             new_state.new_record_level = None;
             new_state.new_record_total = false;
-            new_state
+            new_state.keep_display_settings(self)
         } else {
-            // Restart from level 1
-            Self::new()
+            self.new_run(clock)
+        }
+    }
+    
+    /// Level 1 of a fresh run, keeping the best times, the chosen maze style
+    /// and display settings.
+    pub fn new_run(&self, clock: &dyn Clock) -> Self {
+        Self::new_level(1, vec![None; 5], self.best_times.clone(), self.best_total_time, 0.0, self.maze_style, clock)
+            .keep_display_settings(self)
+    }
+    
+    /// Regenerates the current level with a different maze style.
+    pub fn with_maze_style(&self, maze_style: MazeStyle, clock: &dyn Clock) -> Self {
+        Self::new_level(
+            self.current_level,
+            self.run_times.clone(),
+            self.best_times.clone(),
+            self.best_total_time,
+            self.total_time,
+            maze_style,
+            clock,
+        )
+        .keep_display_settings(self)
+    }
+    
+    /// Carries the player's display choices over from `previous` into a
+    /// freshly generated level.
+    fn keep_display_settings(mut self, previous: &GameState) -> Self {
+        self.glyph_ramp = previous.glyph_ramp.clone();
+        self.render_mode = previous.render_mode;
+        self.dither_method = previous.dither_method;
+        self.dither_config = previous.dither_config;
+        self.hud = previous.hud;
+        self.purist = previous.purist;
+        self
+    }
+    
+    pub fn new_level(level: u8, run_times: Vec<Option<f64>>, best_times: Vec<Option<f64>>, best_total_time: Option<f64>, total_time: f64, maze_style: MazeStyle, clock: &dyn Clock) -> Self {
+        GameState {
+            total_time,
+            run_times,
+            best_times,
+            best_total_time,
+            ..Self::start_level(level, maze_style, random_seed(), clock)
+        }
+    }
+    
+    /// Plays the current level again on the same maze, racing the ghost of
+    /// the best run on it. A finished attempt no longer counts towards the run.
+    pub fn retry_level(&self, clock: &dyn Clock) -> Self {
+        let mut run_times = self.run_times.clone();
+        let mut total_time = self.total_time;
+        if let Some(time) = self.level_completion_time {
+            run_times[(self.current_level - 1) as usize] = None;
+            total_time -= time;
         }
+        GameState {
+            total_time,
+            run_times,
+            best_times: self.best_times.clone(),
+            best_total_time: self.best_total_time,
+            ..Self::start_level(self.current_level, self.maze_style, self.seed, clock)
+        }
+        .keep_display_settings(self)
+    }
+    
+    /// Starts the level a replay was recorded on, as a new run, with the
+    /// replay as the ghost to race.
+    pub fn race(&self, replay: Replay, clock: &dyn Clock) -> Self {
+        let mut state = GameState {
+            best_times: self.best_times.clone(),
+            best_total_time: self.best_total_time,
+            ..Self::start_level(replay.level, replay.maze_style, replay.seed, clock)
+        }
+        .keep_display_settings(self);
+        state.ghost = Some(Ghost::new(replay));
+        state
+    }
+    
+    // Starts a level on the maze `seed` generates, as the player sees it,
+    // with the start banner timed
+    fn start_level(level: u8, maze_style: MazeStyle, seed: u64, clock: &dyn Clock) -> Self {
+        let mut state = Self::from_seed(level, maze_style, seed);
+        state.level_start_time = clock.now();
+        state.events.push(GameEvent::LevelStarted { level });
+        state
     }
     
-    pub fn new_level(level: u8, run_times: Vec<Option<f64>>, best_times: Vec<Option<f64>>, best_total_time: Option<f64>, total_time: f64) -> Self {
+    /// The start of `level` on the maze `seed` generates, with no recorded
+    /// times, starting at clock reading 0.0. The same arguments always give
+    /// the same state, and nothing is read from or written to disk, so a
+    /// replay can rebuild the level it was recorded on.
+    pub fn from_seed(level: u8, maze_style: MazeStyle, seed: u64) -> Self {
         let maze_size = (7 + level as usize) as usize; // 8, 9, 10, 11, 12
-        let maze = Maze::new(maze_size, maze_size);
-        // Use the random exit position from maze generation
-        let exit_x = maze.exit.0 as f64 + 0.5;
-        let exit_y = maze.exit.1 as f64 + 0.5;
+        let thin_maze = match maze_style {
+            MazeStyle::Blocks => None,
+            MazeStyle::ThinWalls => Some(ThinMaze::with_seed(maze_size, maze_size, seed)),
+        };
+        let maze = match &thin_maze {
+            Some(thin) => thin.to_block_maze(),
+            None => Maze::with_seed(maze_size, maze_size, seed),
+        };
         
         // Use the start position from maze generation
         let start = maze.start;
-        let end = maze.exit;
         
-        // Save maze map to file
-        Self::save_maze_map(&maze, start, end);
+        // Use the random start and exit positions from maze generation
+        // Thin-wall mazes are walked in their own cell coordinates, not the block expansion
+        let ((start_x, start_y), (exit_x, exit_y)) = match &thin_maze {
+            Some(thin) => (
+                (thin.start.0 as f64 + 0.5, thin.start.1 as f64 + 0.5),
+                (thin.exit.0 as f64 + 0.5, thin.exit.1 as f64 + 0.5),
+            ),
+            None => (
+                (start.0 as f64 + 0.5, start.1 as f64 + 0.5),
+                (maze.exit.0 as f64 + 0.5, maze.exit.1 as f64 + 0.5),
+            ),
+        };
         
         // Calculate initial angle to face an open direction from start position
-        let start_x = start.0 as f64 + 0.5;
-        let start_y = start.1 as f64 + 0.5;
-        
+        // (the block expansion has the same open directions as the thin-wall layout)
         let mut initial_angle = 0.0;
         // Check which directions are open from the random start position
         if start.0 + 1 < maze.width && !maze.is_wall(start.0 + 1, start.1) {
@@ -140,13 +320,7 @@ impl GameState {
             initial_angle = dy.atan2(dx);
         }
         
-        // Get current time in seconds
-        let level_start_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64();
-        
-        GameState {
+        let mut state = GameState {
             player_x: start_x,
             player_y: start_y,
             player_angle: initial_angle,
@@ -155,21 +329,87 @@ impl GameState {
             exit_y,
             has_won: false,
             current_level: level,
-            level_start_time,
+            seed,
+            level_start_time: 0.0,
+            level_elapsed: 0.0,
+            tick_accumulator: 0.0,
+            previous_pose: None,
+            pending_mouse: 0.0,
+            replay: Replay::new(level, maze_style, seed),
+            is_replay: false,
+            ghost: None,
+            events: Vec::new(),
             level_completion_time: None,
-            total_time,
-            run_times,
-            best_times,
-            best_total_time,
+            total_time: 0.0,
+            run_times: vec![None; 5],
+            best_times: vec![None; 5],
+            best_total_time: None,
             new_record_level: None,
             new_record_total: false,
-        }
+            maze_style,
+            thin_maze,
+            glyph_ramp: GlyphRamp::default(),
+            render_mode: RenderMode::default(),
+            dither_method: DitherMethod::default(),
+            dither_config: DitherConfig::default(),
+            hud: Hud::default(),
+            seen: vec![vec![false; maze.width]; maze.height],
+            purist: false,
+            built_maze: OnceLock::from(maze),
+        };
+        // What can be seen from the start is on the map from the start
+        state.explore();
+        state
     }
     
-    pub fn save_best_times(best_times: &[Option<f64>], best_total_time: Option<f64>) {
-        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        path.pop(); // Go from src-tauri to app/
-        path.push("best_times.json");
+    /// A level-1 state on a given maze, facing east from its start, with no
+    /// recorded times, starting at clock reading 0.0. Nothing is read from or
+    /// written to disk, so tests and benchmarks get the same state every run.
+    pub fn with_maze(maze: &Maze) -> Self {
+        let mut state = GameState {
+            player_x: maze.start.0 as f64 + 0.5,
+            player_y: maze.start.1 as f64 + 0.5,
+            player_angle: 0.0,
+            maze: MazeData::from(maze),
+            exit_x: maze.exit.0 as f64 + 0.5,
+            exit_y: maze.exit.1 as f64 + 0.5,
+            has_won: false,
+            current_level: 1,
+            seed: 0,
+            level_start_time: 0.0,
+            level_elapsed: 0.0,
+            tick_accumulator: 0.0,
+            previous_pose: None,
+            pending_mouse: 0.0,
+            replay: Replay::default(),
+            is_replay: false,
+            ghost: None,
+            events: Vec::new(),
+            level_completion_time: None,
+            total_time: 0.0,
+            run_times: vec![None; 5],
+            best_times: vec![None; 5],
+            best_total_time: None,
+            new_record_level: None,
+            new_record_total: false,
+            maze_style: MazeStyle::Blocks,
+            thin_maze: None,
+            glyph_ramp: GlyphRamp::default(),
+            render_mode: RenderMode::default(),
+            dither_method: DitherMethod::default(),
+            dither_config: DitherConfig::default(),
+            hud: Hud::default(),
+            seen: vec![vec![false; maze.width]; maze.height],
+            purist: false,
+            built_maze: OnceLock::from(maze.clone()),
+        };
+        state.explore();
+        state
+    }
+    
+    pub fn save_best_times(dir: &Path, best_times: &[Option<f64>], best_total_time: Option<f64>) -> Result<(), GameError> {
+        fs::create_dir_all(dir).map_err(|e| GameError::io(dir, e))?;
+        let path = dir.join("best_times.json");
         
         const CURRENT_VERSION: &str = "1.2.3";
         
@@ -179,15 +419,11 @@ impl GameState {
             "best_total_time": best_total_time,
         });
         
-        if let Err(e) = fs::write(&path, serde_json::to_string_pretty(&data).unwrap()) {
-            eprintln!("Failed to save best times: {}", e);
-        }
+        fs::write(&path, serde_json::to_string_pretty(&data).unwrap()).map_err(|e| GameError::io(&path, e))
     }
     
-    pub fn load_best_times() -> (Vec<Option<f64>>, Option<f64>) {
-        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        path.pop(); // Go from src-tauri to app/
-        path.push("best_times.json");
+    pub fn load_best_times(dir: &Path) -> (Vec<Option<f64>>, Option<f64>) {
+        let path = dir.join("best_times.json");
         
         const CURRENT_VERSION: &str = "1.2.3";
         
@@ -227,10 +463,12 @@ impl GameState {
         (vec![None; 5], None)
     }
     
-    fn save_maze_map(maze: &Maze, start: (usize, usize), end: (usize, usize)) {
-        use std::fs::File;
-        use std::io::Write;
-        use std::path::PathBuf;
+    /// Writes the level's maze to `app/maze_map.txt`, with the start as `P`
+    /// and the exit as `E`. Levels only do this as they start with the
+    /// `maze-map` feature, for debugging.
+    pub fn save_maze_map(&self) -> Result<(), GameError> {
+        let maze = self.block_maze();
+        let (start, end) = (maze.start, maze.exit);
         
         // Get the app directory (go up from src-tauri to app/)
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -247,7 +485,16 @@ impl GameState {
                 } else if (x, y) == end {
                     output.push('E');
                 } else if maze.is_wall(x, y) {
-                    output.push('█');
+                    let wall_height = maze.wall_height(x, y);
+                    if maze.material(x, y).is_see_through() {
+                        output.push('░');
+                    } else if wall_height < FULL_WALL_HEIGHT {
+                        output.push('▄');
+                    } else if wall_height > FULL_WALL_HEIGHT {
+                        output.push('▓');
+                    } else {
+                        output.push('█');
+                    }
                 } else {
                     output.push(' ');
                 }
@@ -255,86 +502,104 @@ impl GameState {
             output.push('\n');
         }
         
-        // Write to file
-        if let Ok(mut file) = File::create(&path) {
-            if let Err(e) = file.write_all(output.as_bytes()) {
-                eprintln!("Failed to write maze map: {}", e);
-            }
-        } else {
-            eprintln!("Failed to create maze map file at: {:?}", path);
-        }
+        fs::write(&path, output).map_err(|e| GameError::io(&path, e))
     }
 
+    /// Advances the game by one frame of real time.
+    /// 
+    /// The simulation itself runs in fixed ticks of `TICK` seconds: the
+    /// frame's time is added to an accumulator and as many whole ticks as fit
+    /// are run, with the remainder carried to the next frame. The same inputs
+    /// and frame times therefore always give the same trajectory, however
+    /// the frames are paced.
+    /// 
+    /// Every tick's input is added to `replay`, so the level can be played
+    /// back exactly.
     pub fn update(&mut self, input: &PlayerInput) {
         // Don't process any input if already won
         if self.has_won {
             return;
         }
         
-        // Use delta time to make movement frame-rate independent
-        // Base speeds are per second, so multiply by delta_time
         // Clamp delta_time to prevent huge jumps (e.g., if tab was inactive)
-        let delta_time = input.delta_time.min(0.1); // Cap at 100ms (10fps minimum)
-        
-        let move_speed_per_second = 1.8; // Units per second (0.03 per frame at 60fps, slower than original 0.05)
-        let turn_speed_per_second = 3.6; // Radians per second (0.06 per frame at 60fps, slower than original 0.10)
-        let move_speed = move_speed_per_second * delta_time;
-        let turn_speed = turn_speed_per_second * delta_time;
-        let maze: Maze = self.maze.clone().into();
-
-        // Handle rotation
-        if input.turn_left {
-            self.player_angle -= turn_speed;
-        }
-        if input.turn_right {
-            self.player_angle += turn_speed;
-        }
-        // Handle mouse/trackpad turning
-        self.player_angle += input.mouse_delta_x * turn_speed_per_second * 2.0 * delta_time;
-
-        // Normalize angle
-        self.player_angle = self.player_angle % (2.0 * std::f64::consts::PI);
-        if self.player_angle < 0.0 {
-            self.player_angle += 2.0 * std::f64::consts::PI;
+        let frame_time = input.delta_time.clamp(0.0, MAX_FRAME_TIME);
+        
+        // Mouse movement waits for the next tick, so it isn't lost on frames
+        // too short to run one and is recorded with the tick that turns by it
+        self.pending_mouse += input.mouse_delta_x;
+        
+        self.tick_accumulator += frame_time;
+        let moved = self.tick_accumulator >= TICK;
+        while self.tick_accumulator >= TICK && !self.has_won {
+            self.previous_pose = Some((self.player_x, self.player_y, self.player_angle));
+            let tick_input = TickInput::new(input, std::mem::take(&mut self.pending_mouse));
+            self.replay.push(tick_input);
+            self.tick(&tick_input);
+            self.tick_accumulator -= TICK;
         }
-
-        // Handle movement
-        let dx = self.player_angle.cos() * move_speed;
-        let dy = self.player_angle.sin() * move_speed;
-
-        if input.forward {
-            let new_x = self.player_x + dx;
-            let new_y = self.player_y + dy;
-            if !maze.get_cell(new_x, new_y) {
-                self.player_x = new_x;
-                self.player_y = new_y;
-            }
+        if moved {
+            self.explore();
         }
-        if input.backward {
-            let new_x = self.player_x - dx;
-            let new_y = self.player_y - dy;
-            if !maze.get_cell(new_x, new_y) {
-                self.player_x = new_x;
-                self.player_y = new_y;
+    }
+    
+    /// The level's maze, as the map and block-style levels use it.
+    pub fn block_maze(&self) -> &Maze {
+        self.built_maze.get_or_init(|| self.maze.clone().into())
+    }
+    
+    // Marks the map cells in view as seen: every cell the rays of the view
+    // pass through, and the walls they stop at
+    fn explore(&mut self) {
+        let (x, y) = self.map_position();
+        let camera = Camera::new(x, y, self.player_angle);
+        let reach = match self.thin_maze {
+            Some(_) => camera.max_distance * 2.0,
+            None => camera.max_distance,
+        };
+        let maze = self.built_maze.get_or_init(|| self.maze.clone().into());
+        for i in 0..=EXPLORE_RAYS {
+            let angle = camera.angle - camera.fov / 2.0 + camera.fov * i as f64 / EXPLORE_RAYS as f64;
+            for (cell_x, cell_y) in cast_ray(x, y, angle, maze, reach, None, None).cells {
+                if cell_x < 0 || cell_y < 0 {
+                    continue;
+                }
+                if let Some(seen) = self.seen.get_mut(cell_y as usize).and_then(|row| row.get_mut(cell_x as usize)) {
+                    *seen = true;
+                }
             }
         }
-        if input.left {
-            let left_angle = self.player_angle - std::f64::consts::PI / 2.0;
-            let new_x = self.player_x + left_angle.cos() * move_speed;
-            let new_y = self.player_y + left_angle.sin() * move_speed;
-            if !maze.get_cell(new_x, new_y) {
-                self.player_x = new_x;
-                self.player_y = new_y;
-            }
+    }
+    
+    /// Where the player is on the map, `maze`'s block grid. Thin-wall mazes
+    /// are walked in their own cells, each two blocks across on the map.
+    pub fn map_position(&self) -> (f64, f64) {
+        match self.thin_maze {
+            Some(_) => (self.player_x * 2.0 + 0.5, self.player_y * 2.0 + 0.5),
+            None => (self.player_x, self.player_y),
         }
-        if input.right {
-            let right_angle = self.player_angle + std::f64::consts::PI / 2.0;
-            let new_x = self.player_x + right_angle.cos() * move_speed;
-            let new_y = self.player_y + right_angle.sin() * move_speed;
-            if !maze.get_cell(new_x, new_y) {
-                self.player_x = new_x;
-                self.player_y = new_y;
-            }
+    }
+    
+    // Runs one fixed step of the simulation
+    fn tick(&mut self, input: &TickInput) {
+        let maze = self.built_maze.get_or_init(|| self.maze.clone().into());
+        let thin_maze = self.thin_maze.as_ref();
+        let is_blocked = |x: f64, y: f64| match thin_maze {
+            Some(thin) => thin.is_blocked(x, y),
+            None => maze.get_cell(x, y),
+        };
+        
+        // The level timer only counts ticks that were played, so a paused or
+        // suspended window doesn't add to the player's time
+        self.level_elapsed += TICK;
+        
+        let (x, y, angle) = walk((self.player_x, self.player_y, self.player_angle), input, &is_blocked);
+        self.player_x = x;
+        self.player_y = y;
+        self.player_angle = angle;
+        
+        if let Some(ghost) = self.ghost.as_mut() {
+            ghost.advance(&is_blocked);
+            ghost.check(x, y, (self.level_elapsed / TICK).round() as usize);
         }
         
         // Check if player reached the exit - stop movement
@@ -348,22 +613,24 @@ impl GameState {
             self.player_y = self.exit_y - dy_to_exit * 0.1;
             
             // Record completion time for this level
-            let current_time = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs_f64();
-            let level_time = current_time - self.level_start_time;
+            let level_time = self.level_elapsed;
             self.level_completion_time = Some(level_time);
             
             // Update best time for this level if it's better
             let level_idx = (self.current_level - 1) as usize;
+            let mut is_record = false;
             if level_idx < self.best_times.len() {
                 if self.best_times[level_idx].is_none() || 
                    self.best_times[level_idx].unwrap() > level_time {
                     self.best_times[level_idx] = Some(level_time);
                     self.new_record_level = Some(self.current_level);
+                    is_record = true;
                 }
             }
+            self.events.push(GameEvent::LevelCompleted { level: self.current_level, time: level_time, record: is_record });
+            if is_record {
+                self.events.push(GameEvent::RecordBroken { record: Record::Level(self.current_level), time: level_time });
+            }
             
             // Store this level's completion time in run_times
             let level_idx = (self.current_level - 1) as usize;
@@ -380,489 +647,308 @@ impl GameState {
                    self.best_total_time.unwrap() > self.total_time {
                     self.best_total_time = Some(self.total_time);
                     self.new_record_total = true;
+                    self.events.push(GameEvent::RecordBroken { record: Record::Total, time: self.total_time });
                 }
             }
-            
-            // Save best times after updating
-            Self::save_best_times(&self.best_times, self.best_total_time);
         }
     }
 
-    pub fn render_frame(&mut self, width: usize, height: usize) -> String {
-        let maze: Maze = self.maze.clone().into();
-        let fov = std::f64::consts::PI / 2.0; // 90 degrees (zoomed out)
-        let max_distance = 20.0;
-        
-        // Continue with normal rendering even if won - we'll overlay message at the end
-        
-        // Pre-calculate raycast results for each column
-        // Store: (distance, wall_type, passed_exit, exit_threshold_dist, hit_x, hit_y)
-        let mut column_data: Vec<(f64, u8, bool, Option<f64>, f64, f64)> = Vec::with_capacity(width);
-        for col in 0..width {
-            let ray_angle = self.player_angle - fov / 2.0 + (col as f64 / width as f64) * fov;
-            let result = cast_ray(
-                self.player_x, 
-                self.player_y, 
-                ray_angle, 
-                &maze, 
-                max_distance,
-                Some(self.exit_x),
-                Some(self.exit_y),
-            );
-            column_data.push((result.distance, result.wall_type, result.passed_exit, result.exit_threshold_dist, result.hit_x, result.hit_y));
-        }
-        
-        let mut frame = String::new();
-        
-        // Create dither pattern (reuse across frame for performance)
-        use crate::dither::DitherPattern;
-        let dither = DitherPattern::new();
-        
-        // Render row by row
-        for row in 0..height {
-            for col in 0..width {
-                let (distance, wall_type, passed_exit, exit_threshold_dist, hit_x, hit_y) = column_data[col];
-                
-                // Calculate wall height based on distance (perspective projection)
-                let wall_render_dist = distance;
-                let wall_height = if wall_render_dist > 0.01 {
-                    (height as f64 / wall_render_dist).min(height as f64 * 2.0)
-                } else {
-                    height as f64 * 2.0
-                };
-                
-                let wall_start = ((height as f64 - wall_height) / 2.0) as usize;
-                let wall_end = (wall_start + wall_height as usize).min(height);
-                
-                if row < wall_start {
-                    // Ceiling
-                    frame.push(' ');
-                } else if row < wall_end {
-                    // Wall - if ray passed through exit threshold, make threshold line invisible
-                    if passed_exit && exit_threshold_dist.is_some() {
-                        let threshold_dist = exit_threshold_dist.unwrap();
-                        // Make threshold line invisible
-                        if (wall_render_dist - threshold_dist).abs() < 0.2 {
-                            frame.push(' '); // Invisible exit threshold line
-                        } else {
-                            // Calculate per-pixel dithering with row position for vertical variation
-                            frame.push(crate::raycast::get_dithered_ascii_char_with_row(
-                                distance,
-                                wall_type,
-                                max_distance,
-                                hit_x,
-                                hit_y,
-                                row as f64,
-                                &dither,
-                            ));
-                        }
-                    } else {
-                        // Calculate per-pixel dithering with row position for vertical variation
-                        frame.push(crate::raycast::get_dithered_ascii_char_with_row(
-                            distance,
-                            wall_type,
-                            max_distance,
-                            hit_x,
-                            hit_y,
-                            row as f64,
-                            &dither,
-                        ));
-                    }
-                } else {
-                    // Floor - stop at exit threshold if ray passed through exit
-                    if passed_exit {
-                        // Check if floor position is before exit threshold
-                        let floor_dist = calculate_floor_distance(
-                            col,
-                            row,
-                            width,
-                            height,
-                            fov,
-                            self.player_angle,
-                        );
-                        if let Some(threshold) = exit_threshold_dist {
-                            if floor_dist < threshold {
-                                frame.push(get_floor_char(floor_dist, max_distance));
-                            } else {
-                                frame.push(' ');
-                            }
-                        } else {
-                            frame.push(' ');
-                        }
-                    } else {
-                        let floor_dist = calculate_floor_distance(
-                            col,
-                            row,
-                            width,
-                            height,
-                            fov,
-                            self.player_angle,
-                        );
-                        if floor_dist < max_distance {
-                            frame.push(get_floor_char(floor_dist, max_distance));
-                        } else {
-                            frame.push(' ');
-                        }
-                    }
-                }
-            }
-            
-            if row < height - 1 {
-                frame.push('\n');
+    /// Takes the events that have happened since this was last called.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+    
+    pub fn render_frame(&self, width: usize, height: usize, clock: &dyn Clock) -> String {
+        self.render_frame_with(width, height, Threading::default(), clock)
+    }
+    
+    /// Where the player is looking from, blended between the last two ticks
+    /// by how far the accumulator is into the next one. This keeps motion
+    /// smooth when the display and the simulation run at different rates.
+    pub fn camera(&self) -> Camera {
+        let current = (self.player_x, self.player_y, self.player_angle);
+        let (x, y, angle) = match self.previous_pose {
+            Some(previous) if !self.has_won => {
+                let alpha = self.interpolation();
+                // Turn the short way round when the angle wraps past 0
+                let turn = normalize_angle(current.2 - previous.2 + std::f64::consts::PI) - std::f64::consts::PI;
+                (
+                    previous.0 + (current.0 - previous.0) * alpha,
+                    previous.1 + (current.1 - previous.1) * alpha,
+                    previous.2 + turn * alpha,
+                )
             }
+            _ => current,
+        };
+        Camera::new(x, y, angle)
+    }
+    
+    // How far the simulation is into the next tick, from 0.0 to 1.0
+    fn interpolation(&self) -> f64 {
+        (self.tick_accumulator / TICK).clamp(0.0, 1.0)
+    }
+    
+    /// The player's display settings, for a frame of the given size.
+    pub fn render_options(&self, width: usize, height: usize, threading: Threading) -> RenderOptions {
+        RenderOptions {
+            width,
+            height,
+            mode: self.render_mode,
+            glyph_ramp: self.glyph_ramp.clone(),
+            dither_method: self.dither_method,
+            dither_config: self.dither_config,
+            threading,
         }
+    }
+    
+    /// Renders a frame, optionally casting columns and shading rows on
+    /// several threads. The output is the same either way.
+    /// 
+    /// The clock only decides whether the start message is showing, so the
+    /// same state and clock reading always give the same frame.
+    pub fn render_frame_with(&self, width: usize, height: usize, threading: Threading, clock: &dyn Clock) -> String {
+        let camera = self.camera();
+        let options = self.render_options(width, height, threading);
+        let mut frame = match &self.thin_maze {
+            Some(thin) => Renderer.render_thin(&camera, thin, &options),
+            None => Renderer.render(&camera, self.block_maze(), &options),
+        };
         
-        // Overlay win message if player has won (using ASCII art)
-        if self.has_won {
-            // Special layout for level 5 - two columns
-            if self.current_level == 5 {
-                return self.render_level_5_win_screen(width, height, &frame);
-            }
-            
-            // ASCII art for "LEVEL COMPLETE!" - for levels 1-4
-            let ascii_art = vec![
-                "██╗     ███████╗██╗   ██╗███████╗██╗         ██████╗ ██████╗ ███╗   ███╗██████╗ ██╗     ███████╗████████╗███████╗",
-                "██║     ██╔════╝██║   ██║██╔════╝██║        ██╔════╝██╔═══██╗████╗ ████║██╔══██╗██║     ██╔════╝╚══██╔══╝██╔════╝",
-                "██║     █████╗  ██║   ██║█████╗  ██║        ██║     ██║   ██║██╔████╔██║██████╔╝██║     █████╗     ██║   █████╗  ",
-                "██║     ██╔══╝  ╚██╗ ██╔╝██╔══╝  ██║        ██║     ██║   ██║██║╚██╔╝██║██╔═══╝ ██║     ██╔══╝     ██║   ██╔══╝  ",
-                "███████╗███████╗ ╚████╔╝ ███████╗███████╗   ╚██████╗╚██████╔╝██║ ╚═╝ ██║██║     ███████╗███████╗   ██║   ███████╗",
-                "╚══════╝╚══════╝  ╚═══╝  ╚══════╝╚══════╝    ╚═════╝ ╚═════╝ ╚═╝     ╚═╝╚═╝     ╚══════╝╚══════╝   ╚═╝   ╚══════╝",
-            ];
-            
-            // Format level completion time
-            let level_time_str = if let Some(time) = self.level_completion_time {
-                let minutes = (time as u64) / 60;
-                let seconds = (time as u64) % 60;
-                let milliseconds = ((time % 1.0) * 100.0) as u64;
-                format!("Time: {:02}:{:02}.{:02}", minutes, seconds, milliseconds)
-            } else {
-                "Time: --:--".to_string()
+        // The ghost of the best run, and how the player compares with it at the last checkpoint
+        let mut overlay = Overlay::new();
+        if let Some(ghost) = &self.ghost {
+            let glyph = match self.render_mode {
+                RenderMode::Glyphs => self.glyph_ramp.glyph(GHOST_BRIGHTNESS),
+                RenderMode::Braille => GHOST_BRAILLE,
             };
-            
-            // Format best time for this level
-            let level_idx = (self.current_level - 1) as usize;
-            let best_level_time_str = if level_idx < self.best_times.len() && self.best_times[level_idx].is_some() {
-                let best = self.best_times[level_idx].unwrap();
-                let minutes = (best as u64) / 60;
-                let seconds = (best as u64) % 60;
-                let milliseconds = ((best % 1.0) * 100.0) as u64;
-                format!("Best: {:02}:{:02}.{:02}", minutes, seconds, milliseconds)
-            } else {
-                "Best: --:--".to_string()
-            };
-            
-            let next_level_str = "Press SPACE to continue";
-            
-            let art_height = ascii_art.len();
-            let art_start_row = (height.saturating_sub(art_height + 4)) / 2;
-            let time_row = art_start_row + art_height + 1;
-            let best_row = time_row + 1;
-            let next_row = best_row + 3; // 2 blank lines after best (best_row + 1, +2, then next_row at +3)
-            
-            // Check for personal best message
-            let personal_best_str = if self.new_record_level == Some(self.current_level) {
-                " PERSONAL BEST!".to_string()
-            } else {
-                String::new()
-            };
-            
-            // Combine time and personal best
-            let time_with_pb = format!("{}{}", level_time_str, personal_best_str);
-            
-            let lines: Vec<&str> = frame.split('\n').collect();
-            let mut new_frame = String::new();
-            
-            for (row_idx, line) in lines.iter().enumerate() {
-                // Ensure line is exactly width characters
-                let mut new_line: Vec<char> = line.chars().take(width).collect();
-                // Pad line to exact width if needed
-                while new_line.len() < width {
-                    new_line.push(' ');
-                }
-                // Truncate if longer (shouldn't happen, but safety check)
-                new_line.truncate(width);
-                
-                // Overlay ASCII art (centered) - always show even with personal best
-                if row_idx >= art_start_row && row_idx < art_start_row + art_height {
-                    let art_line_idx = row_idx - art_start_row;
-                    if art_line_idx < ascii_art.len() {
-                        let art_line = ascii_art[art_line_idx];
-                        let art_start_col = if art_line.len() <= width {
-                            (width - art_line.len()) / 2
-                        } else {
-                            0
-                        };
-                        for (i, ch) in art_line.chars().enumerate() {
-                            let col_idx = art_start_col + i;
-                            if col_idx < width {
-                                new_line[col_idx] = ch;
-                            }
-                        }
-                    }
-                }
-                // Overlay level time message with personal best
-                else if row_idx == time_row {
-                    let time_start_col = width.saturating_sub(time_with_pb.len()) / 2;
-                    for (i, ch) in time_with_pb.chars().enumerate() {
-                        let col_idx = time_start_col + i;
-                        if col_idx < width {
-                            new_line[col_idx] = ch;
-                        }
-                    }
-                }
-                // Overlay best level time
-                else if row_idx == best_row {
-                    let best_start_col = width.saturating_sub(best_level_time_str.len()) / 2;
-                    for (i, ch) in best_level_time_str.chars().enumerate() {
-                        let col_idx = best_start_col + i;
-                        if col_idx < width {
-                            new_line[col_idx] = ch;
-                        }
-                    }
-                }
-                // Overlay next level message at bottom
-                else if row_idx == next_row {
-                    let next_start_col = width.saturating_sub(next_level_str.len()) / 2;
-                    for (i, ch) in next_level_str.chars().enumerate() {
-                        let col_idx = next_start_col + i;
-                        if col_idx < width {
-                            new_line[col_idx] = ch;
-                        }
-                    }
-                }
-                
-                new_frame.push_str(&new_line.iter().collect::<String>());
-                if row_idx < lines.len() - 1 {
-                    new_frame.push('\n');
-                }
+            Renderer.draw_sprite(&mut frame, &camera, &ghost.sprite(self.interpolation(), glyph));
+            if let Some(split) = ghost.split_text(self.level_elapsed) {
+                overlay.push(Layer { anchor: Anchor::TopRight, margin: (1, 2), ..Layer::new([split]) });
             }
-            return new_frame;
         }
         
-        // Overlay start message that flashes for 3 seconds
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64();
-        let elapsed = current_time - self.level_start_time;
-        if elapsed < 3.0 {
-            // Flash: show for 0.5s, hide for 0.3s, repeat
-            let flash_cycle = 0.8; // 0.5s on + 0.3s off
-            let phase = (elapsed % flash_cycle) / flash_cycle;
-            if phase < 0.625 { // Show for 62.5% of cycle (0.5s / 0.8s)
-                let message = format!("LEVEL {} - FIND THE EXIT!", self.current_level);
-                let message_row = height / 2;
-                let message_start_col = width.saturating_sub(message.len()) / 2;
-            
-            let lines: Vec<&str> = frame.split('\n').collect();
-            let mut new_frame = String::new();
-            for (row_idx, line) in lines.iter().enumerate() {
-                if row_idx == message_row {
-                        // Overlay "FIND THE EXIT!" message
-                    let mut new_line = line.chars().collect::<Vec<_>>();
-                    for (i, ch) in message.chars().enumerate() {
-                        if message_start_col + i < new_line.len() {
-                            new_line[message_start_col + i] = ch;
-                        }
-                    }
-                    new_frame.push_str(&new_line.iter().collect::<String>());
-                } else {
-                    new_frame.push_str(line);
-                }
-                if row_idx < lines.len() - 1 {
-                    new_frame.push('\n');
-                }
-            }
-            return new_frame;
+        if self.has_won {
+            overlay.push(self.win_screen(width));
+        } else {
+            overlay.layers.extend(self.hud.layers(self));
+            if Self::start_message_showing(clock.now() - self.level_start_time) {
+                let mut lines = banner(&format!("LEVEL {}", self.current_level), width, Font::Big);
+                lines.extend([String::new(), "FIND THE EXIT!".to_string()]);
+                overlay.push(Layer::new(lines));
             }
         }
-        
-        frame
-    }
-    
-    fn render_level_5_win_screen(&self, width: usize, height: usize, frame: &str) -> String {
-        // ASCII art for "LEVEL COMPLETE!" - always show
-        let ascii_art = vec![
-            "██╗     ███████╗██╗   ██╗███████╗██╗         ██████╗ ██████╗ ███╗   ███╗██████╗ ██╗     ███████╗████████╗███████╗",
-            "██║     ██╔════╝██║   ██║██╔════╝██║        ██╔════╝██╔═══██╗████╗ ████║██╔══██╗██║     ██╔════╝╚══██╔══╝██╔════╝",
-            "██║     █████╗  ██║   ██║█████╗  ██║        ██║     ██║   ██║██╔████╔██║██████╔╝██║     █████╗     ██║   █████╗  ",
-            "██║     ██╔══╝  ╚██╗ ██╔╝██╔══╝  ██║        ██║     ██║   ██║██║╚██╔╝██║██╔═══╝ ██║     ██╔══╝     ██║   ██╔══╝  ",
-            "███████╗███████╗ ╚████╔╝ ███████╗███████╗   ╚██████╗╚██████╔╝██║ ╚═╝ ██║██║     ███████╗███████╗   ██║   ███████╗",
-            "╚══════╝╚══════╝  ╚═══╝  ╚══════╝╚══════╝    ╚═════╝ ╚═════╝ ╚═╝     ╚═╝╚═╝     ╚══════╝╚══════╝   ╚═╝   ╚══════╝",
-        ];
-        
-        // Format level 5 time
-        let level_5_time_str = if let Some(time) = self.level_completion_time {
-            let minutes = (time as u64) / 60;
-            let seconds = (time as u64) % 60;
-            let milliseconds = ((time % 1.0) * 100.0) as u64;
-            format!("{:02}:{:02}.{:02}", minutes, seconds, milliseconds)
-        } else {
-            "--:--".to_string()
-        };
-        
-        // Check for personal best and append to time
-        let personal_best_suffix = if self.new_record_level == Some(5) || self.new_record_total {
-            " PERSONAL BEST!"
-        } else {
-            ""
-        };
-        let time_with_pb = format!("Time: {}{}", level_5_time_str, personal_best_suffix);
-        
-        // Format best time for level 5
-        let level_5_best_str = if self.best_times.len() > 4 && self.best_times[4].is_some() {
-            let best = self.best_times[4].unwrap();
-            let minutes = (best as u64) / 60;
-            let seconds = (best as u64) % 60;
-            let milliseconds = ((best % 1.0) * 100.0) as u64;
-            format!("{:02}:{:02}.{:02}", minutes, seconds, milliseconds)
-        } else {
-            "--:--".to_string()
-        };
-        
-        // Format times for levels 1-4 (use actual run times, not best times)
-        let format_time = |time_opt: Option<f64>| -> String {
-            if let Some(time) = time_opt {
-                let minutes = (time as u64) / 60;
-                let seconds = (time as u64) % 60;
-                let milliseconds = ((time % 1.0) * 100.0) as u64;
-                format!("{:02}:{:02}.{:02}", minutes, seconds, milliseconds)
-            } else {
-                "--:--".to_string()
+        overlay.draw(&mut frame.cells);
+        frame.to_string()
+    }
+    
+    // The start message flashes for the first 3 seconds of a level: shown
+    // for 0.5s, hidden for 0.3s, repeat
+    fn start_message_showing(elapsed: f64) -> bool {
+        let flash_cycle = 0.8;
+        elapsed < 3.0 && (elapsed % flash_cycle) / flash_cycle < 0.625
+    }
+    
+    // "LEVEL COMPLETE" with the level's time and best, and "NEW RECORD" under
+    // it if either is a personal best; after level 5, the times of the whole
+    // run as well. The banners shrink to fit `width` columns.
+    fn win_screen(&self, width: usize) -> Layer {
+        let level_idx = (self.current_level - 1) as usize;
+        let best_time = self.best_times.get(level_idx).copied().flatten();
+        let personal_best = self.new_record_level == Some(self.current_level) || (self.current_level == 5 && self.new_record_total);
+        
+        let mut lines = banner("LEVEL COMPLETE", width, Font::Big);
+        lines.push(String::new());
+        if personal_best {
+            lines.extend(banner("NEW RECORD", width, Font::Small));
+            lines.push(String::new());
+        }
+        lines.push(format!("Time: {}", format_time(self.level_completion_time)));
+        lines.push(format!("Best: {}", format_time(best_time)));
+        lines.push(String::new());
+        
+        if self.current_level == 5 {
+            // Times for levels 1-4 are this run's, not the bests
+            for level in 1..=4 {
+                let time = self.run_times.get(level - 1).copied().flatten();
+                lines.push(format!("Level {}: {}", level, format_time(time)));
             }
-        };
-        
-        let level_1_time = if self.run_times.len() > 0 { format_time(self.run_times[0]) } else { "--:--".to_string() };
-        let level_2_time = if self.run_times.len() > 1 { format_time(self.run_times[1]) } else { "--:--".to_string() };
-        let level_3_time = if self.run_times.len() > 2 { format_time(self.run_times[2]) } else { "--:--".to_string() };
-        let level_4_time = if self.run_times.len() > 3 { format_time(self.run_times[3]) } else { "--:--".to_string() };
-        
-        // Format total time
-        let total_time_str = {
-            let minutes = (self.total_time as u64) / 60;
-            let seconds = (self.total_time as u64) % 60;
-            let milliseconds = ((self.total_time % 1.0) * 100.0) as u64;
-            format!("{:02}:{:02}.{:02}", minutes, seconds, milliseconds)
-        };
-        
-        // Format best total time with personal best indicator
-        let best_total_pb_suffix = if self.new_record_total {
-            " PERSONAL BEST!"
-        } else {
-            ""
-        };
-        let best_total_str = if let Some(best) = self.best_total_time {
-            let minutes = (best as u64) / 60;
-            let seconds = (best as u64) % 60;
-            let milliseconds = ((best % 1.0) * 100.0) as u64;
-            format!("{:02}:{:02}.{:02}{}", minutes, seconds, milliseconds, best_total_pb_suffix)
+            lines.push(String::new());
+            lines.push(format!("Total: {}", format_time(Some(self.total_time))));
+            lines.push(format!("Best total: {}", format_time(self.best_total_time)));
+            lines.push(String::new());
+            lines.push(String::new());
+            lines.push("Press SPACE to play again".to_string());
         } else {
-            format!("--:--{}", best_total_pb_suffix)
-        };
-        
-        // All texts in single column (centered)
-        let texts = vec![
-            time_with_pb,
-            format!("Best: {}", level_5_best_str),
-            String::new(), // Empty line
-            format!("Level 1: {}", level_1_time),
-            format!("Level 2: {}", level_2_time),
-            format!("Level 3: {}", level_3_time),
-            format!("Level 4: {}", level_4_time),
-            String::new(), // Empty line between level 4 and total
-            format!("Total: {}", total_time_str),
-            format!("Best total: {}", best_total_str),
-            String::new(), // Empty line
-            String::new(), // Empty line (2 total)
-            "Press SPACE to play again".to_string(),
-        ];
-        
-        // Calculate starting row (center vertically, accounting for ASCII art)
-        let art_height = ascii_art.len();
-        let total_text_lines = texts.len();
-        let art_start_row = (height.saturating_sub(art_height + total_text_lines + 1)) / 2;
-        let start_row = art_start_row + art_height + 1;
-        
-        let lines: Vec<&str> = frame.split('\n').collect();
-        let mut new_frame = String::new();
-        
-        for (row_idx, line) in lines.iter().enumerate() {
-            // Ensure line is exactly width characters
-            let mut new_line: Vec<char> = line.chars().take(width).collect();
-            // Pad line to exact width if needed
-            while new_line.len() < width {
-                new_line.push(' ');
-            }
-            // Truncate if longer (shouldn't happen, but safety check)
-            new_line.truncate(width);
-            
-            // Overlay ASCII art (centered) - always show
-            if row_idx >= art_start_row && row_idx < art_start_row + art_height {
-                let art_line_idx = row_idx - art_start_row;
-                if art_line_idx < ascii_art.len() {
-                    let art_line = ascii_art[art_line_idx];
-                    let art_start_col = if art_line.len() <= width {
-                        (width - art_line.len()) / 2
-                    } else {
-                        0
-                    };
-                    for (i, ch) in art_line.chars().enumerate() {
-                        let col_idx = art_start_col + i;
-                        if col_idx < width {
-                            new_line[col_idx] = ch;
-                        }
-                    }
-                }
-            }
-            
-            // Overlay texts (centered)
-            if row_idx >= start_row && row_idx < start_row + texts.len() {
-                let text_idx = row_idx - start_row;
-                if text_idx < texts.len() {
-                    let text = &texts[text_idx];
-                    if !text.is_empty() {
-                        let text_start_col = width.saturating_sub(text.len()) / 2;
-                        for (i, ch) in text.chars().enumerate() {
-                            let col_idx = text_start_col + i;
-                            if col_idx < width {
-                                new_line[col_idx] = ch;
-                            }
-                        }
-                    }
-                }
-            }
-            
-            new_frame.push_str(&new_line.iter().collect::<String>());
-            if row_idx < lines.len() - 1 {
-                new_frame.push('\n');
-            }
+            lines.push(String::new());
+            lines.push("Press SPACE to continue".to_string());
         }
-        
-        new_frame
+        Layer::new(lines)
     }
 }
 
-fn calculate_floor_distance(
-    _col: usize,
-    row: usize,
-    _width: usize,
-    height: usize,
-    _fov: f64,
-    _player_angle: f64,
-) -> f64 {
-    let p = (row as f64 - height as f64 / 2.0) / (height as f64 / 2.0);
-    let distance = 1.0 / p.max(0.1);
-    distance
+
+/// Formats a time in seconds as `mm:ss.cc`, or `--:--` when there isn't one.
+pub fn format_time(time: Option<f64>) -> String {
+    match time {
+        Some(time) => format!("{:02}:{:02}.{:02}", (time as u64) / 60, (time as u64) % 60, ((time % 1.0) * 100.0) as u64),
+        None => "--:--".to_string(),
+    }
 }
 
-fn get_floor_char(distance: f64, max_distance: f64) -> char {
-    let normalized_dist = (distance / max_distance).min(1.0);
-    if normalized_dist < 0.3 {
-        '.'
-    } else if normalized_dist < 0.6 {
-        ','
-    } else {
-        ' '
+/// Moves a player at `pose` (x, y and angle) through one tick of `input`,
+/// stopping short of anything `is_blocked` reports. Ghosts replaying a run
+/// move with the same steps, so they retrace it exactly.
+pub(crate) fn walk(pose: (f64, f64, f64), input: &TickInput, is_blocked: &dyn Fn(f64, f64) -> bool) -> (f64, f64, f64) {
+    let (mut x, mut y, mut angle) = pose;
+    let move_speed = MOVE_SPEED * TICK;
+    let turn_speed = TURN_SPEED * TICK;
+    
+    // Handle rotation
+    angle += input.mouse * MOUSE_SENSITIVITY;
+    if input.turn_left() {
+        angle -= turn_speed;
+    }
+    if input.turn_right() {
+        angle += turn_speed;
+    }
+    angle = normalize_angle(angle);
+
+    // Handle movement
+    let dx = angle.cos() * move_speed;
+    let dy = angle.sin() * move_speed;
+
+    if input.forward() {
+        let new_x = x + dx;
+        let new_y = y + dy;
+        if !is_blocked(new_x, new_y) {
+            x = new_x;
+            y = new_y;
+        }
+    }
+    if input.backward() {
+        let new_x = x - dx;
+        let new_y = y - dy;
+        if !is_blocked(new_x, new_y) {
+            x = new_x;
+            y = new_y;
+        }
     }
+    if input.left() {
+        let left_angle = angle - std::f64::consts::PI / 2.0;
+        let new_x = x + left_angle.cos() * move_speed;
+        let new_y = y + left_angle.sin() * move_speed;
+        if !is_blocked(new_x, new_y) {
+            x = new_x;
+            y = new_y;
+        }
+    }
+    if input.right() {
+        let right_angle = angle + std::f64::consts::PI / 2.0;
+        let new_x = x + right_angle.cos() * move_speed;
+        let new_y = y + right_angle.sin() * move_speed;
+        if !is_blocked(new_x, new_y) {
+            x = new_x;
+            y = new_y;
+        }
+    }
+    
+    (x, y, angle)
+}
+
+// Wraps an angle into [0, 2π)
+fn normalize_angle(angle: f64) -> f64 {
+    angle.rem_euclid(2.0 * std::f64::consts::PI)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::test_mazes;
+    
+    fn idle(delta_time: f64) -> PlayerInput {
+        PlayerInput {
+            forward: false,
+            backward: false,
+            left: false,
+            right: false,
+            turn_left: false,
+            turn_right: false,
+            mouse_delta_x: 0.0,
+            delta_time,
+        }
+    }
+    
+    #[test]
+    fn test_level_timer_counts_played_frames_only() {
+        let mut state = GameState::with_maze(&Maze::with_seed(8, 8, 0x5eed));
+        for _ in 0..60 {
+            state.update(&idle(0.016));
+        }
+        // A suspended window comes back with a huge delta, which only counts as one slow frame
+        state.update(&idle(3600.0));
+        let ticks = ((60.0 * 0.016 + MAX_FRAME_TIME) / TICK).floor();
+        assert!((state.level_elapsed - ticks * TICK).abs() < 1e-9);
+    }
+    
+    // A 12x3 corridor running east, with nothing to stop the player for 10 units
+    fn corridor() -> Maze {
+        test_mazes::corridor(12, 11)
+    }
+    
+    // Holds forward and weaves left and right, one input per frame
+    fn run(frame_times: &[f64]) -> GameState {
+        let mut state = GameState::with_maze(&corridor());
+        for (frame, &delta_time) in frame_times.iter().enumerate() {
+            let weave = (frame / 20) % 2 == 0;
+            state.update(&PlayerInput { forward: true, turn_left: weave, turn_right: !weave, ..idle(delta_time) });
+        }
+        state
+    }
+    
+    #[test]
+    fn test_same_inputs_give_same_trajectory() {
+        // Uneven frame pacing, as from a real display
+        let frame_times: Vec<f64> = (0..120).map(|i| 0.010 + (i % 7) as f64 * 0.002).collect();
+        let first = run(&frame_times);
+        let second = run(&frame_times);
+        assert_eq!(
+            (first.player_x, first.player_y, first.player_angle, first.level_elapsed),
+            (second.player_x, second.player_y, second.player_angle, second.level_elapsed)
+        );
+    }
+    
+    #[test]
+    fn test_frame_rate_does_not_change_distance() {
+        // Two seconds of walking straight ahead, at 30, 60 and 144 frames per second
+        let walk = |fps: usize| {
+            let mut state = GameState::with_maze(&corridor());
+            for _ in 0..fps * 2 {
+                state.update(&PlayerInput { forward: true, ..idle(1.0 / fps as f64) });
+            }
+            state.player_x
+        };
+        let expected = walk(60);
+        assert!((expected - (1.5 + MOVE_SPEED * 2.0)).abs() <= MOVE_SPEED * TICK + 1e-9);
+        assert!((walk(30) - expected).abs() <= MOVE_SPEED * TICK + 1e-9);
+        assert!((walk(144) - expected).abs() <= MOVE_SPEED * TICK + 1e-9);
+    }
+    
+    #[test]
+    fn test_camera_interpolates_between_ticks() {
+        let mut state = GameState::with_maze(&corridor());
+        state.update(&PlayerInput { forward: true, ..idle(TICK * 1.5) });
+        let (previous_x, _, _) = state.previous_pose.unwrap();
+        let camera = state.camera();
+        assert!((camera.x - (previous_x + state.player_x) / 2.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_start_banner_follows_clock() {
+        let state = GameState::with_maze(&Maze::with_seed(8, 8, 0x5eed));
+        let clock = MockClock::new(0.1);
+        assert!(state.render_frame_with(60, 20, Threading::Single, &clock).contains("FIND THE EXIT"));
+        clock.advance(5.0);
+        assert!(!state.render_frame_with(60, 20, Threading::Single, &clock).contains("FIND THE EXIT"));
+    }
+}
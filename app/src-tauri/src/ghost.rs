@@ -0,0 +1,259 @@
+use crate::error::GameError;
+use crate::game::{walk, GameState, MazeStyle, TICK};
+use crate::renderer::Sprite;
+use crate::replay::{Replay, ReplayPlayer};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// New cells along the ghost's path between one checkpoint and the next
+const CHECKPOINT_SPACING: usize = 3;
+// Seconds a split stays on screen after a checkpoint
+const SPLIT_DISPLAY_TIME: f64 = 3.0;
+// Size of the ghost figure: a little narrower than a corridor and shorter than a wall
+const GHOST_WIDTH: f64 = 0.5;
+const GHOST_HEIGHT: f64 = 0.8;
+// Most ghosts kept at once. Every level gets a new random seed, so without
+// a limit the ghosts of mazes that will never come back would pile up
+const MAX_GHOSTS: usize = 16;
+
+/// A place on the ghost's path where the player's time is compared with it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub cell: (usize, usize),
+    pub tick: usize, // When the ghost first entered the cell
+    pub reached: bool, // True once the player has entered it too
+}
+
+/// How the player's time compared with the ghost's at a checkpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Split {
+    pub delta: f64, // Seconds behind the ghost; negative when ahead
+    pub at: f64, // Level time the checkpoint was reached
+}
+
+/// The best run on a maze, retraced tick for tick alongside the player.
+/// 
+/// The ghost moves with the same steps as the player, so it follows the
+/// recorded run exactly without storing its path. Checkpoints are laid
+/// along that path when the ghost is created.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Ghost {
+    pub replay: Replay,
+    pub pose: (f64, f64, f64), // x, y and angle
+    pub previous: (f64, f64), // Position before the last tick, for interpolation
+    pub cursor: (usize, u32), // Run of the replay being played, and ticks played of it
+    pub checkpoints: Vec<Checkpoint>,
+    pub split: Option<Split>, // Latest checkpoint comparison
+}
+
+impl Ghost {
+    /// A ghost at the start of the level `replay` was recorded on.
+    pub fn new(replay: Replay) -> Self {
+        Self::starting_from(ReplayPlayer::start(&replay), replay)
+    }
+    
+    // A ghost of `replay` played from `state`
+    fn starting_from(mut state: GameState, replay: Replay) -> Self {
+        let start = (state.player_x, state.player_y, state.player_angle);
+        
+        // Play the run through once to find where the ghost goes and when
+        let mut visited = HashSet::from([cell_at(start.0, start.1)]);
+        let mut checkpoints = Vec::new();
+        for (tick, input) in replay.inputs().enumerate() {
+            ReplayPlayer::play(&mut state, &input);
+            let cell = cell_at(state.player_x, state.player_y);
+            if visited.insert(cell) && visited.len() % CHECKPOINT_SPACING == 0 {
+                checkpoints.push(Checkpoint { cell, tick: tick + 1, reached: false });
+            }
+        }
+        
+        Ghost {
+            replay,
+            pose: start,
+            previous: (start.0, start.1),
+            cursor: (0, 0),
+            checkpoints,
+            split: None,
+        }
+    }
+    
+    /// The ghost of the best run kept in `dir` for a maze, if there is one.
+    pub fn best(dir: &Path, level: u8, maze_style: MazeStyle, seed: u64) -> Option<Self> {
+        Replay::load(&best_path(dir, level, maze_style, seed)).ok().map(Self::new)
+    }
+    
+    /// Moves the ghost through its next recorded tick. Once the run is
+    /// over it stays where it finished.
+    pub fn advance(&mut self, is_blocked: &dyn Fn(f64, f64) -> bool) {
+        self.previous = (self.pose.0, self.pose.1);
+        let (run, played) = self.cursor;
+        let Some(&(input, ticks)) = self.replay.runs.get(run) else {
+            return;
+        };
+        self.pose = walk(self.pose, &input, is_blocked);
+        self.cursor = if played + 1 < ticks { (run, played + 1) } else { (run + 1, 0) };
+    }
+    
+    /// Compares the player's time with the ghost's when the player first
+    /// enters one of its checkpoints.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x`, `y` - The player's position
+    /// * `tick` - Ticks the player has played on the level
+    pub fn check(&mut self, x: f64, y: f64, tick: usize) {
+        let cell = cell_at(x, y);
+        if let Some(checkpoint) = self.checkpoints.iter_mut().find(|c| c.cell == cell && !c.reached) {
+            checkpoint.reached = true;
+            self.split = Some(Split {
+                delta: (tick as f64 - checkpoint.tick as f64) * TICK,
+                at: tick as f64 * TICK,
+            });
+        }
+    }
+    
+    /// The latest split, while it's still shown, e.g. `AHEAD -0.42s`.
+    pub fn split_text(&self, elapsed: f64) -> Option<String> {
+        let split = self.split.filter(|split| elapsed - split.at < SPLIT_DISPLAY_TIME)?;
+        let label = match split.delta {
+            delta if delta < 0.0 => "AHEAD",
+            delta if delta > 0.0 => "BEHIND",
+            _ => "EVEN",
+        };
+        Some(format!("{} {:+.2}s", label, split.delta))
+    }
+    
+    /// The ghost figure, blended between its last two ticks by `alpha`.
+    pub fn sprite(&self, alpha: f64, glyph: char) -> Sprite {
+        Sprite {
+            x: self.previous.0 + (self.pose.0 - self.previous.0) * alpha,
+            y: self.previous.1 + (self.pose.1 - self.previous.1) * alpha,
+            width: GHOST_WIDTH,
+            height: GHOST_HEIGHT,
+            glyph,
+        }
+    }
+}
+
+/// Keeps a finished run in `dir` as the ghost for its maze if it beat the
+/// run kept so far, or if there wasn't one. Only the `MAX_GHOSTS` most
+/// recently saved ghosts are kept.
+pub fn save_if_best(dir: &Path, replay: &Replay) -> Result<(), GameError> {
+    let path = best_path(dir, replay.level, replay.maze_style, replay.seed);
+    let is_best = Replay::load(&path).map_or(true, |best| replay.len() < best.len());
+    if is_best {
+        replay.save(&path)?;
+        prune(&path)?;
+    }
+    Ok(())
+}
+
+// Where the best run on a maze is kept: `ghosts/` in the save directory
+fn best_path(dir: &Path, level: u8, maze_style: MazeStyle, seed: u64) -> PathBuf {
+    dir.join("ghosts").join(format!("level-{}-{:?}-{:016x}.txt", level, maze_style, seed))
+}
+
+// Deletes the oldest ghosts beside `latest` until only `MAX_GHOSTS` are left
+fn prune(latest: &Path) -> Result<(), GameError> {
+    let Some(ghosts) = latest.parent() else {
+        return Ok(());
+    };
+    let mut older: Vec<_> = fs::read_dir(ghosts)
+        .map_err(|e| GameError::io(ghosts, e))?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let saved = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+            (path != latest && path.extension() == Some("txt".as_ref())).then_some((saved, path))
+        })
+        .collect();
+    older.sort_by_key(|&(saved, _)| Reverse(saved));
+    for (_, path) in older.into_iter().skip(MAX_GHOSTS - 1) {
+        fs::remove_file(&path).map_err(|e| GameError::io(&path, e))?;
+    }
+    Ok(())
+}
+
+fn cell_at(x: f64, y: f64) -> (usize, usize) {
+    (x.max(0.0) as usize, y.max(0.0) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerInput;
+    use crate::replay::TickInput;
+    use crate::test_mazes;
+    
+    // A 12x3 corridor running east, with the exit at the far end
+    fn corridor() -> GameState {
+        GameState::with_maze(&test_mazes::corridor(12, 11))
+    }
+    
+    // Four seconds down the corridor, stopping now and then, short of the exit
+    fn record() -> Replay {
+        let mut state = corridor();
+        for frame in 0..240 {
+            state.update(&PlayerInput {
+                forward: frame % 50 < 40,
+                delta_time: 1.0 / 60.0,
+                ..PlayerInput::default()
+            });
+        }
+        assert!(!state.has_won);
+        state.replay
+    }
+    
+    #[test]
+    fn test_ghost_retraces_run_and_reports_splits() {
+        let replay = record();
+        
+        // Racing a ghost of the same run, the player is level with it at every checkpoint
+        let mut state = corridor();
+        state.ghost = Some(Ghost::starting_from(corridor(), replay.clone()));
+        assert!(state.ghost.as_ref().unwrap().checkpoints.len() >= 2);
+        for input in replay.inputs() {
+            ReplayPlayer::play(&mut state, &input);
+        }
+        let ghost = state.ghost.as_ref().unwrap();
+        assert_eq!(ghost.pose, (state.player_x, state.player_y, state.player_angle));
+        assert!(ghost.checkpoints.iter().all(|c| c.reached));
+        assert_eq!(ghost.split_text(state.level_elapsed).unwrap(), "EVEN +0.00s");
+        assert_eq!(ghost.split_text(state.level_elapsed + SPLIT_DISPLAY_TIME), None);
+    }
+    
+    #[test]
+    fn test_split_shows_time_behind() {
+        let mut ghost = Ghost::starting_from(corridor(), record());
+        let checkpoint = ghost.checkpoints[0].clone();
+        let (x, y) = (checkpoint.cell.0 as f64 + 0.5, checkpoint.cell.1 as f64 + 0.5);
+        ghost.check(x, y, checkpoint.tick + 60);
+        assert_eq!(ghost.split_text(checkpoint.tick as f64 * TICK + 0.5).unwrap(), "BEHIND +0.50s");
+        // Each checkpoint only counts once
+        ghost.split = None;
+        ghost.check(x, y, checkpoint.tick);
+        assert_eq!(ghost.split, None);
+    }
+    
+    #[test]
+    fn test_only_the_best_runs_on_the_latest_mazes_are_kept() {
+        let dir = std::env::temp_dir().join(format!("matrix-maze-ghosts-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let run = record();
+        let mut slower = run.clone();
+        slower.push(TickInput::default());
+        save_if_best(&dir, &run).unwrap();
+        save_if_best(&dir, &slower).unwrap();
+        assert_eq!(Replay::load(&best_path(&dir, run.level, run.maze_style, run.seed)).unwrap(), run);
+        
+        // Each maze is another seed; past the limit the oldest are dropped
+        for seed in 1..=MAX_GHOSTS as u64 {
+            save_if_best(&dir, &Replay { seed, ..run.clone() }).unwrap();
+        }
+        assert_eq!(fs::read_dir(dir.join("ghosts")).unwrap().count(), MAX_GHOSTS);
+        assert!(Ghost::best(&dir, run.level, run.maze_style, MAX_GHOSTS as u64).is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+
+/// A glyph and how much of its cell it inks, from 0.0 (blank) to 1.0 (solid).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Glyph {
+    pub ch: char,
+    pub coverage: f64,
+}
+
+/// An ordered set of characters used to draw shaded surfaces.
+/// 
+/// Wall glyphs are sorted from faintest to densest, and the brightness at
+/// which the renderer switches from one to the next is the midpoint of their
+/// ink coverage, so a ramp of any length or any character set spreads over
+/// the brightness range the way it looks on screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlyphRamp {
+    pub name: String,
+    pub wall: Vec<Glyph>, // Faintest first
+    pub thresholds: Vec<f64>, // wall.len() - 1 brightness cut-offs between neighbouring glyphs
+    pub floor: Vec<char>, // Nearest first; beyond the last the floor is blank
+}
+
+impl GlyphRamp {
+    /// Names of the built-in ramps, in the order the game cycles through them.
+    pub const BUILT_IN: [&'static str; 4] = ["blocks", "ascii", "braille", "katakana"];
+    
+    /// Block shading characters - the original look.
+    pub fn blocks() -> Self {
+        Self::from_glyphs("blocks", "█▓▒░·", ".,").unwrap()
+    }
+    
+    /// Pure 7-bit ASCII, for terminals and fonts without box drawing.
+    pub fn ascii() -> Self {
+        Self::from_glyphs("ascii", ".:-=+*%@#", ".,").unwrap()
+    }
+    
+    /// Braille patterns with one to eight raised dots.
+    pub fn braille() -> Self {
+        Self::from_glyphs("braille", "⠁⠃⠇⡇⡏⡟⡿⣿", "⠄⠂").unwrap()
+    }
+    
+    /// Half-width Katakana, for a "Matrix rain" look.
+    pub fn katakana() -> Self {
+        Self::from_glyphs("katakana", "･ｰﾉｼﾂﾜﾐﾓ", "･･").unwrap()
+    }
+    
+    /// Builds a ramp from characters whose coverage is known.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `name` - Display name of the ramp
+    /// * `wall` - Wall glyphs in any order
+    /// * `floor` - Floor glyphs, nearest first
+    /// 
+    /// # Returns
+    /// 
+    /// The ramp, or an error naming the first wall glyph with no coverage data
+    pub fn from_glyphs(name: &str, wall: &str, floor: &str) -> Result<Self, String> {
+        let glyphs = wall
+            .chars()
+            .map(|ch| {
+                ink_coverage(ch)
+                    .map(|coverage| Glyph { ch, coverage })
+                    .ok_or_else(|| format!("No coverage data for '{}'; give it as `coverage {} = <0.0-1.0>`", ch, ch))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::with_coverage(name, glyphs, floor.chars().collect())
+    }
+    
+    /// Builds a ramp from glyphs with explicit coverage values.
+    pub fn with_coverage(name: &str, mut wall: Vec<Glyph>, floor: Vec<char>) -> Result<Self, String> {
+        if wall.is_empty() {
+            return Err("A glyph ramp needs at least one wall glyph".to_string());
+        }
+        if let Some(glyph) = wall.iter().find(|g| !(0.0..=1.0).contains(&g.coverage)) {
+            return Err(format!("Coverage for '{}' must be between 0.0 and 1.0", glyph.ch));
+        }
+        
+        wall.sort_by(|a, b| a.coverage.total_cmp(&b.coverage));
+        
+        // Scale so the densest glyph stands for full brightness, then split halfway between neighbours
+        let max_coverage = wall.last().unwrap().coverage.max(f64::EPSILON);
+        let thresholds = wall
+            .windows(2)
+            .map(|pair| (pair[0].coverage + pair[1].coverage) / 2.0 / max_coverage)
+            .collect();
+        
+        Ok(Self {
+            name: name.to_string(),
+            wall,
+            thresholds,
+            floor,
+        })
+    }
+    
+    /// Loads a ramp from a built-in name or a ramp definition.
+    /// 
+    /// A definition has one `key = value` per line; `#` starts a comment:
+    /// 
+    /// ```text
+    /// name = dots
+    /// wall = .oO@
+    /// floor = .,
+    /// coverage o = 0.3
+    /// ```
+    /// 
+    /// `coverage` lines are only needed for characters the game has no
+    /// measurements for.
+    pub fn load(spec: &str) -> Result<Self, String> {
+        match spec.trim() {
+            "blocks" => return Ok(Self::blocks()),
+            "ascii" => return Ok(Self::ascii()),
+            "braille" => return Ok(Self::braille()),
+            "katakana" => return Ok(Self::katakana()),
+            _ => {}
+        }
+        
+        let mut name = "custom".to_string();
+        let mut wall = None;
+        let mut floor = String::new();
+        let mut overrides = Vec::new();
+        
+        for (number, line) in spec.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Line {}: expected `key = value`", number + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+            
+            if key == "name" {
+                name = value.to_string();
+            } else if key == "wall" {
+                wall = Some(value.to_string());
+            } else if key == "floor" {
+                floor = value.to_string();
+            } else if let Some(glyph) = key.strip_prefix("coverage") {
+                let mut chars = glyph.trim().chars();
+                let ch = match (chars.next(), chars.next()) {
+                    (Some(ch), None) => ch,
+                    _ => return Err(format!("Line {}: `coverage` takes exactly one character", number + 1)),
+                };
+                let coverage = value
+                    .parse::<f64>()
+                    .map_err(|_| format!("Line {}: `{}` is not a number", number + 1, value))?;
+                overrides.push(Glyph { ch, coverage });
+            } else {
+                return Err(format!("Line {}: unknown key `{}`", number + 1, key));
+            }
+        }
+        
+        let wall = wall.ok_or_else(|| format!("Unknown glyph ramp `{}`", spec.trim()))?;
+        let glyphs = wall
+            .chars()
+            .map(|ch| {
+                let coverage = overrides
+                    .iter()
+                    .find(|g| g.ch == ch)
+                    .map(|g| g.coverage)
+                    .or_else(|| ink_coverage(ch))
+                    .ok_or_else(|| format!("No coverage data for '{}'; add `coverage {} = <0.0-1.0>`", ch, ch))?;
+                Ok(Glyph { ch, coverage })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        
+        Self::with_coverage(&name, glyphs, floor.chars().collect())
+    }
+    
+    /// The brightness each wall glyph stands for, faintest first.
+    /// 
+    /// `glyph` maps each of these back to its own glyph, so a ditherer that
+    /// snaps to these levels picks glyphs exactly.
+    pub fn levels(&self) -> Vec<f64> {
+        let max_coverage = self.wall.last().unwrap().coverage.max(f64::EPSILON);
+        self.wall.iter().map(|g| g.coverage / max_coverage).collect()
+    }
+    
+    /// Picks the wall glyph for a brightness in [0.0, 1.0].
+    pub fn glyph(&self, brightness: f64) -> char {
+        let index = self.thresholds.iter().take_while(|&&t| brightness > t).count();
+        self.wall[index].ch
+    }
+    
+    /// Picks the floor glyph for a distance normalized to [0.0, 1.0].
+    /// 
+    /// The floor fades out over the nearest 60% of the view distance, split
+    /// evenly between the floor glyphs.
+    pub fn floor_glyph(&self, normalized_dist: f64) -> char {
+        let band = (normalized_dist / 0.6 * self.floor.len() as f64).floor() as usize;
+        self.floor.get(band).copied().unwrap_or(' ')
+    }
+}
+
+impl Default for GlyphRamp {
+    fn default() -> Self {
+        Self::blocks()
+    }
+}
+
+/// Returns the fraction of a character cell the glyph inks, if known.
+/// 
+/// Block elements and braille are measured from their geometry. Other
+/// glyphs are measured by counting lit pixels in a 5x7 bitmap font.
+pub fn ink_coverage(ch: char) -> Option<f64> {
+    if let Some(coverage) = block_coverage(ch) {
+        return Some(coverage);
+    }
+    
+    // Braille: the low eight bits of the code point are the raised dots
+    let code = ch as u32;
+    if (0x2800..=0x28FF).contains(&code) {
+        return Some((code - 0x2800).count_ones() as f64 / 8.0);
+    }
+    
+    BITMAPS
+        .iter()
+        .find(|(c, _)| *c == ch)
+        .map(|(_, rows)| rows.iter().map(|r| r.count_ones()).sum::<u32>() as f64 / 35.0)
+}
+
+fn block_coverage(ch: char) -> Option<f64> {
+    let coverage = match ch {
+        '█' => 1.0,
+        '▓' => 0.75,
+        '▒' => 0.5,
+        '░' => 0.25,
+        '▀' | '▄' | '▌' | '▐' | '▚' | '▞' => 0.5,
+        '▖' | '▗' | '▘' | '▝' => 0.25,
+        '▙' | '▛' | '▜' | '▟' => 0.75,
+        // Lower eighths ▁..▇ and left eighths ▉..▏
+        '\u{2581}'..='\u{2587}' => (ch as u32 - 0x2580) as f64 / 8.0,
+        '\u{2589}'..='\u{258F}' => (0x2590 - ch as u32) as f64 / 8.0,
+        ' ' => 0.0,
+        _ => return None,
+    };
+    Some(coverage)
+}
+
+// 5x7 bitmaps, one byte per row, bit 4 is the leftmost column
+const BITMAPS: [(char, [u8; 7]); 21] = [
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000]),
+    ('·', [0b00000, 0b00000, 0b00000, 0b01100, 0b00000, 0b00000, 0b00000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('*', [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000]),
+    ('o', [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011]),
+    ('@', [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110]),
+    ('#', [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010]),
+    // Half-width Katakana
+    ('･', [0b00000, 0b00000, 0b00000, 0b00100, 0b00000, 0b00000, 0b00000]),
+    ('ｰ', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('ﾉ', [0b00001, 0b00001, 0b00010, 0b00010, 0b00100, 0b01000, 0b10000]),
+    ('ｼ', [0b11000, 0b00001, 0b11001, 0b00010, 0b00010, 0b00100, 0b11000]),
+    ('ﾂ', [0b10101, 0b10101, 0b00001, 0b00010, 0b00010, 0b00100, 0b11000]),
+    ('ﾜ', [0b11111, 0b10001, 0b10001, 0b00001, 0b00010, 0b00100, 0b11000]),
+    ('ﾐ', [0b11100, 0b00011, 0b11100, 0b00011, 0b00000, 0b11100, 0b00011]),
+    ('ﾓ', [0b11111, 0b00100, 0b11111, 0b00100, 0b00100, 0b00100, 0b00011]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_built_in_ramps_are_ordered() {
+        for name in GlyphRamp::BUILT_IN {
+            let ramp = GlyphRamp::load(name).unwrap();
+            assert_eq!(ramp.thresholds.len(), ramp.wall.len() - 1);
+            assert!(ramp.thresholds.windows(2).all(|t| t[0] <= t[1]), "{}", name);
+            
+            // Darkest and brightest ends of the ramp
+            assert_eq!(ramp.glyph(0.0), ramp.wall[0].ch);
+            assert_eq!(ramp.glyph(1.0), ramp.wall.last().unwrap().ch);
+        }
+        
+        let blocks = GlyphRamp::blocks();
+        assert_eq!(blocks.glyph(1.0), '█');
+        assert_eq!(blocks.glyph(0.5), '▒');
+        assert_eq!(blocks.glyph(0.0), '·');
+    }
+    
+    #[test]
+    fn test_built_in_ramps_have_distinct_coverages() {
+        // Two glyphs with the same coverage would leave one of them unused
+        for name in GlyphRamp::BUILT_IN {
+            let ramp = GlyphRamp::load(name).unwrap();
+            assert!(ramp.wall.windows(2).all(|pair| pair[0].coverage < pair[1].coverage), "{}", name);
+        }
+    }
+    
+    #[test]
+    fn test_load_custom_ramp() {
+        let ramp = GlyphRamp::load("name = dots\n# faint to dense\nwall = @o.\nfloor = ,\ncoverage ¤ = 0.4\n").unwrap();
+        assert_eq!(ramp.name, "dots");
+        assert_eq!(ramp.wall.iter().map(|g| g.ch).collect::<String>(), ".o@");
+        assert_eq!(ramp.floor_glyph(0.0), ',');
+        assert_eq!(ramp.floor_glyph(0.9), ' ');
+        
+        let with_override = GlyphRamp::load("wall = ¤█").unwrap_err();
+        assert!(with_override.contains('¤'));
+        assert!(GlyphRamp::load("wall = ¤█\ncoverage ¤ = 0.4").is_ok());
+        assert!(GlyphRamp::load("no-such-ramp").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::automap;
+use crate::game::{format_time, GameState};
+use crate::overlay::{Align, Anchor, Border, Layer};
+use serde::{Deserialize, Serialize};
+
+// Cells the minimap shows on each side of the player
+const MINIMAP_RADIUS: i64 = 5;
+// Degrees of heading between neighbouring marks on the compass, and how many marks it shows
+const COMPASS_STEP: i64 = 15;
+const COMPASS_MARKS: i64 = 25;
+
+/// Which parts of the heads-up display are drawn over the view while a
+/// level is being played. All are off until the player turns them on.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Hud {
+    pub level: bool, // Level number, top left
+    pub timer: bool, // Time on the level so far
+    pub best: bool, // Best time on the level, to compare with
+    pub compass: bool, // Heading strip, top center
+    pub minimap: bool, // Explored maze round the player, bottom right
+}
+
+/// One part of the HUD, as the settings page toggles them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudElement {
+    Level,
+    Timer,
+    Best,
+    Compass,
+    Minimap,
+}
+
+impl Hud {
+    pub fn shows(&self, element: HudElement) -> bool {
+        match element {
+            HudElement::Level => self.level,
+            HudElement::Timer => self.timer,
+            HudElement::Best => self.best,
+            HudElement::Compass => self.compass,
+            HudElement::Minimap => self.minimap,
+        }
+    }
+    
+    pub fn toggle(&mut self, element: HudElement) {
+        let shown = match element {
+            HudElement::Level => &mut self.level,
+            HudElement::Timer => &mut self.timer,
+            HudElement::Best => &mut self.best,
+            HudElement::Compass => &mut self.compass,
+            HudElement::Minimap => &mut self.minimap,
+        };
+        *shown = !*shown;
+    }
+    
+    /// The HUD for `game` as it is now, one layer per corner it uses.
+    pub fn layers(&self, game: &GameState) -> Vec<Layer> {
+        let mut layers = Vec::new();
+        
+        let mut stats = Vec::new();
+        if self.level {
+            stats.push(format!("LEVEL {}", game.current_level));
+        }
+        if self.timer {
+            stats.push(format!("TIME {}", format_time(Some(game.level_elapsed))));
+        }
+        if self.best {
+            let best = game.best_times.get((game.current_level - 1) as usize).copied().flatten();
+            stats.push(format!("BEST {}", format_time(best)));
+        }
+        if !stats.is_empty() {
+            layers.push(Layer { anchor: Anchor::TopLeft, align: Align::Left, margin: (1, 2), ..Layer::new(stats) });
+        }
+        
+        if self.compass {
+            layers.push(Layer { anchor: Anchor::Top, margin: (1, 0), ..Layer::new(compass(game.player_angle)) });
+        }
+        if self.minimap {
+            layers.push(Layer {
+                anchor: Anchor::BottomRight,
+                margin: (1, 2),
+                border: Some(Border::Single),
+                opaque: true,
+                ..Layer::new(minimap(game))
+            });
+        }
+        layers
+    }
+}
+
+// Bearing the player faces, in whole degrees clockwise from north. An angle
+// of 0 faces east, and angles grow towards south, as the maze is laid out
+fn heading(angle: f64) -> i64 {
+    (angle.to_degrees() + 90.0).round().rem_euclid(360.0) as i64
+}
+
+// A strip of compass marks centered on the heading, and the heading in degrees under it
+fn compass(angle: f64) -> [String; 2] {
+    let heading = heading(angle);
+    let nearest = (heading as f64 / COMPASS_STEP as f64).round() as i64 * COMPASS_STEP;
+    let strip = (0..COMPASS_MARKS)
+        .map(|i| match (nearest + (i - COMPASS_MARKS / 2) * COMPASS_STEP).rem_euclid(360) {
+            0 => 'N',
+            90 => 'E',
+            180 => 'S',
+            270 => 'W',
+            bearing if bearing % 45 == 0 => '+',
+            _ => '·',
+        })
+        .collect();
+    [strip, format!("{:03}", heading)]
+}
+
+// The cells round the player as far as they've been seen, two characters
+// to a cell so they come out roughly square, with the player as an arrow
+// the way they're facing
+fn minimap(game: &GameState) -> Vec<String> {
+    let (x, y) = game.map_position();
+    let (px, py) = (x.floor() as i64, y.floor() as i64);
+    (py - MINIMAP_RADIUS..=py + MINIMAP_RADIUS)
+        .map(|y| {
+            (px - MINIMAP_RADIUS..=px + MINIMAP_RADIUS)
+                .map(|x| {
+                    if (x, y) == (px, py) {
+                        format!("{} ", automap::arrow(game.player_angle))
+                    } else {
+                        automap::cell_glyph(game, x, y).to_string().repeat(2)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+    use std::f64::consts::PI;
+    
+    #[test]
+    fn test_compass_follows_the_heading() {
+        assert_eq!(heading(0.0), 90); // Facing east
+        assert_eq!(heading(-PI / 2.0), 0);
+        assert_eq!(heading(PI), 270);
+        let [strip, degrees] = compass(-PI / 2.0);
+        assert_eq!(strip.chars().nth(12), Some('N'));
+        assert_eq!(strip.chars().nth(18), Some('E'));
+        assert_eq!(strip.chars().nth(6), Some('W'));
+        assert_eq!(degrees, "000");
+    }
+    
+    #[test]
+    fn test_only_elements_turned_on_are_drawn() {
+        let mut game = GameState::with_maze(&Maze::with_seed(11, 11, 0x5eed));
+        game.level_elapsed = 83.5;
+        let mut hud = Hud::default();
+        assert!(hud.layers(&game).is_empty());
+        
+        hud.toggle(HudElement::Timer);
+        hud.toggle(HudElement::Minimap);
+        let layers = hud.layers(&game);
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].lines, vec!["TIME 01:23.50"]);
+        // The minimap is centered on the player, who starts facing east
+        let minimap = &layers[1].lines;
+        assert_eq!(minimap.len(), 2 * MINIMAP_RADIUS as usize + 1);
+        assert_eq!(minimap[MINIMAP_RADIUS as usize].chars().nth(2 * MINIMAP_RADIUS as usize), Some('→'));
+        hud.toggle(HudElement::Timer);
+        assert!(!hud.shows(HudElement::Timer) && hud.shows(HudElement::Minimap));
+    }
+}
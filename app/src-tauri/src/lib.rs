@@ -0,0 +1,26 @@
+pub mod automap;
+pub mod braille;
+pub mod clock;
+pub mod commands;
+pub mod dither;
+pub mod error;
+pub mod events;
+pub mod flow;
+pub mod font;
+pub mod game;
+pub mod ghost;
+pub mod glyphs;
+pub mod hud;
+pub mod maze;
+pub mod overlay;
+pub mod parallel;
+pub mod raycast;
+pub mod renderer;
+pub mod replay;
+pub mod session;
+#[cfg(test)]
+mod snapshots;
+pub mod stability;
+#[cfg(test)]
+mod test_mazes;
+pub mod thin_maze;
@@ -3,50 +3,122 @@
     windows_subsystem = "windows"
 )]
 
-mod dither;
-mod game;
-mod maze;
-mod raycast;
+use matrix_maze::commands;
+use matrix_maze::dither::{DitherConfig, DitherMethod};
+use matrix_maze::error::GameError;
+use matrix_maze::flow::FlowKey;
+use matrix_maze::game::{MazeStyle, PlayerInput};
+use matrix_maze::renderer::RenderMode;
+use matrix_maze::replay::PlaybackControl;
+use matrix_maze::session::{GameView, SessionId, Sessions};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
 
-use game::{GameState, PlayerInput};
+// Each command hands its arguments to the matching function in `commands`
 
 #[tauri::command]
-fn init_game() -> String {
-    let game_state = GameState::new();
-    serde_json::to_string(&game_state).unwrap()
+fn init_game(sessions: State<Mutex<Sessions>>) -> GameView {
+    commands::init_game(&sessions)
 }
 
 #[tauri::command]
-fn update_game(state_json: String, input: PlayerInput) -> String {
-    let mut game_state: GameState = serde_json::from_str(&state_json).unwrap();
-    game_state.update(&input);
-    serde_json::to_string(&game_state).unwrap()
+fn close_game(sessions: State<Mutex<Sessions>>, session: SessionId) -> Result<(), GameError> {
+    commands::close_game(&sessions, session)
 }
 
 #[tauri::command]
-fn render_frame(state_json: String, width: usize, height: usize) -> (String, String) {
-    let mut game_state: GameState = serde_json::from_str(&state_json).unwrap();
-    let frame = game_state.render_frame(width, height);
-    // Return both the frame and the updated state (in case freeze frame was captured)
-    (frame, serde_json::to_string(&game_state).unwrap())
+fn update_game(sessions: State<Mutex<Sessions>>, session: SessionId, input: PlayerInput) -> Result<GameView, GameError> {
+    commands::update_game(&sessions, session, input)
 }
 
 #[tauri::command]
-fn restart_game() -> String {
-    let game_state = GameState::new();
-    serde_json::to_string(&game_state).unwrap()
+fn render_frame(sessions: State<Mutex<Sessions>>, session: SessionId, width: usize, height: usize) -> Result<String, GameError> {
+    commands::render_frame(&sessions, session, width, height)
 }
 
 #[tauri::command]
-fn next_level(state_json: String) -> String {
-    let game_state: GameState = serde_json::from_str(&state_json).unwrap();
-    let next_state = game_state.next_level();
-    serde_json::to_string(&next_state).unwrap()
+fn restart_game(sessions: State<Mutex<Sessions>>, session: SessionId) -> Result<GameView, GameError> {
+    commands::restart_game(&sessions, session)
+}
+
+#[tauri::command]
+fn press_key(sessions: State<Mutex<Sessions>>, session: SessionId, key: FlowKey) -> Result<GameView, GameError> {
+    commands::press_key(&sessions, session, key)
+}
+
+#[tauri::command]
+fn race_replay(sessions: State<Mutex<Sessions>>, session: SessionId, replay: String) -> Result<GameView, GameError> {
+    commands::race_replay(&sessions, session, &replay)
+}
+
+#[tauri::command]
+fn set_maze_style(sessions: State<Mutex<Sessions>>, session: SessionId, style: MazeStyle) -> Result<GameView, GameError> {
+    commands::set_maze_style(&sessions, session, style)
+}
+
+#[tauri::command]
+fn set_glyph_ramp(sessions: State<Mutex<Sessions>>, session: SessionId, ramp: String) -> Result<GameView, GameError> {
+    commands::set_glyph_ramp(&sessions, session, &ramp)
+}
+
+#[tauri::command]
+fn set_render_mode(sessions: State<Mutex<Sessions>>, session: SessionId, mode: RenderMode) -> Result<GameView, GameError> {
+    commands::set_render_mode(&sessions, session, mode)
+}
+
+#[tauri::command]
+fn set_dither_method(sessions: State<Mutex<Sessions>>, session: SessionId, method: DitherMethod) -> Result<GameView, GameError> {
+    commands::set_dither_method(&sessions, session, method)
+}
+
+#[tauri::command]
+fn set_dither_config(sessions: State<Mutex<Sessions>>, session: SessionId, config: DitherConfig) -> Result<GameView, GameError> {
+    commands::set_dither_config(&sessions, session, config)
+}
+
+#[tauri::command]
+fn save_replay(sessions: State<Mutex<Sessions>>, session: SessionId) -> Result<String, GameError> {
+    commands::save_replay(&sessions, session)
+}
+
+#[tauri::command]
+fn watch_replay(sessions: State<Mutex<Sessions>>, session: SessionId) -> Result<GameView, GameError> {
+    commands::watch_replay(&sessions, session)
+}
+
+#[tauri::command]
+fn load_replay(sessions: State<Mutex<Sessions>>, session: SessionId, replay: String) -> Result<GameView, GameError> {
+    commands::load_replay(&sessions, session, &replay)
+}
+
+#[tauri::command]
+fn control_replay(sessions: State<Mutex<Sessions>>, session: SessionId, control: PlaybackControl) -> Result<GameView, GameError> {
+    commands::control_replay(&sessions, session, control)
+}
+
+#[tauri::command]
+fn stop_replay(sessions: State<Mutex<Sessions>>, session: SessionId) -> Result<GameView, GameError> {
+    commands::stop_replay(&sessions, session)
 }
 
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![init_game, update_game, render_frame, restart_game, next_level])
+        .setup(|app| {
+            // Every game's events go to the frontend as `game-event`s, and its
+            // best runs are kept with the app's data
+            let handle = app.handle().clone();
+            let save_dir = app.path().app_data_dir()?;
+            app.manage(Mutex::new(
+                Sessions::with_listener(move |event| {
+                    if let Err(e) = handle.emit("game-event", event) {
+                        eprintln!("Failed to send game event: {}", e);
+                    }
+                })
+                .saving_to(save_dir),
+            ));
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![init_game, close_game, update_game, render_frame, restart_game, press_key, race_replay, set_maze_style, set_glyph_ramp, set_render_mode, set_dither_method, set_dither_config, save_replay, watch_replay, load_replay, control_replay, stop_replay])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,56 +1,129 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 // Static counter to ensure unique seeds even on fast restarts
 static MAZE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
+// Wall heights are measured in multiples of a standard wall (eye level sits at 0.5)
+pub const FULL_WALL_HEIGHT: f64 = 1.0;
+pub const HALF_WALL_HEIGHT: f64 = 0.4; // Low enough for the player to see over
+pub const PILLAR_HEIGHT: f64 = 1.8;
+
+/// What a wall cell is made of. Every material blocks movement; only solid
+/// walls block sight completely.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum WallMaterial {
+    #[default]
+    Solid,
+    Grate, // Bars with gaps between them
+    Glass, // Clear pane
+    Window, // Solid frame around a glass pane
+    Door { open: f64 }, // Slid aside by `open` (0.0-1.0) of its width
+}
+
+impl WallMaterial {
+    pub fn is_see_through(&self) -> bool {
+        match self {
+            WallMaterial::Solid => false,
+            WallMaterial::Door { open } => *open > 0.0,
+            _ => true,
+        }
+    }
+}
+
+/// Generates a random seed for maze generation.
+/// 
+/// Combines a high-precision timestamp with a global counter, so mazes built
+/// in quick succession (e.g. on fast restarts) still differ.
+pub fn random_seed() -> u64 {
+    // Combine high-precision timestamp (nanoseconds) with a counter to ensure uniqueness
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap();
+    let nanos = now.as_nanos() as u64;
+    
+    // Increment counter atomically to ensure each maze gets a unique seed
+    let counter = MAZE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    
+    // Combine timestamp with counter and additional entropy
+    use std::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+    let mut hasher = DefaultHasher::new();
+    nanos.hash(&mut hasher);
+    counter.hash(&mut hasher);
+    std::thread::current().id().hash(&mut hasher);
+    std::process::id().hash(&mut hasher);
+    
+    hasher.finish()
+}
+
+#[derive(Debug, Clone)]
 pub struct Maze {
     pub width: usize,
     pub height: usize,
     pub cells: Vec<Vec<bool>>, // true = wall, false = empty
+    pub heights: Vec<Vec<f64>>, // Wall height per cell (ignored for empty cells)
+    pub materials: Vec<Vec<WallMaterial>>, // Wall material per cell (ignored for empty cells)
     pub start: (usize, usize), // Starting position
     pub exit: (usize, usize), // Exit position
+    tallest_wall: f64, // Measured once the layout is built, for every ray to read
 }
 
 impl Maze {
     pub fn new(width: usize, height: usize) -> Self {
+        Self::with_seed(width, height, random_seed())
+    }
+    
+    /// A maze with a ready-made layout, as big as its grid of `cells`.
+    /// `heights` and `materials` are indexed the same way.
+    pub fn from_layout(
+        cells: Vec<Vec<bool>>,
+        heights: Vec<Vec<f64>>,
+        materials: Vec<Vec<WallMaterial>>,
+        start: (usize, usize),
+        exit: (usize, usize),
+    ) -> Self {
+        let mut maze = Maze {
+            width: cells.first().map_or(0, Vec::len),
+            height: cells.len(),
+            cells,
+            heights,
+            materials,
+            start,
+            exit,
+            tallest_wall: FULL_WALL_HEIGHT,
+        };
+        maze.tallest_wall = maze.measure_tallest_wall();
+        maze
+    }
+    
+    /// Generates the maze `seed` describes. The same size and seed always
+    /// give the same layout, start and exit.
+    pub fn with_seed(width: usize, height: usize, seed: u64) -> Self {
         let mut maze = Maze {
             width,
             height,
             cells: vec![vec![true; width]; height],
-            start: (1, 1), // Default, will be set in generate()
-            exit: (width - 2, height - 1), // Default, will be set in generate()
+            heights: vec![vec![FULL_WALL_HEIGHT; width]; height],
+            materials: vec![vec![WallMaterial::Solid; width]; height],
+            start: (1, 1), // Default, will be set in carve()
+            exit: (width - 2, height - 1), // Default, will be set in carve()
+            tallest_wall: FULL_WALL_HEIGHT,
         };
-        maze.generate();
+        let rng_seed = maze.carve(seed);
+        maze.decorate_walls(rng_seed);
+        maze.tallest_wall = maze.measure_tallest_wall();
         maze
     }
 
-    fn generate(&mut self) {
+    // Carves the corridors, start and exit out of solid rock, returning the
+    // random state for decorating the walls
+    fn carve(&mut self, mut rng_seed: u64) -> u64 {
         // Recursive backtracking algorithm
         let mut stack: Vec<(usize, usize)> = Vec::new();
         let mut visited: HashSet<(usize, usize)> = HashSet::new();
         
-        // Generate a more random seed using multiple entropy sources
-        // Combine high-precision timestamp (nanoseconds) with a counter to ensure uniqueness
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap();
-        let nanos = now.as_nanos() as u64;
-        
-        // Increment counter atomically to ensure each maze gets a unique seed
-        let counter = MAZE_COUNTER.fetch_add(1, Ordering::Relaxed);
-        
-        // Combine timestamp with counter and additional entropy
-        use std::hash::{Hash, Hasher};
-        use std::collections::hash_map::DefaultHasher;
-        let mut hasher = DefaultHasher::new();
-        nanos.hash(&mut hasher);
-        counter.hash(&mut hasher);
-        std::thread::current().id().hash(&mut hasher);
-        std::process::id().hash(&mut hasher);
-        
-        let mut rng_seed = hasher.finish();
-        
         // Pick a random edge (0=top, 1=right, 2=bottom, 3=left)
         rng_seed = rng_seed.wrapping_mul(1103515245).wrapping_add(12345);
         let edge = rng_seed as usize % 4;
@@ -189,6 +262,70 @@ impl Maze {
                 self.cells[exit.1][exit.0 + 1] = false;
             }
         }
+        
+        rng_seed
+    }
+
+    /// Varies the shape of interior walls once the layout is carved.
+    /// 
+    /// Some walls separating two corridors become half-walls the player can
+    /// see over, or see-through grates, glass, windows and jammed half-open doors.
+    /// Some corner posts become tall pillars, and some walls with a single
+    /// open side are hollowed out into recessed alcoves. Only interior
+    /// cells are touched, so the outer boundary and the exit stay intact.
+    fn decorate_walls(&mut self, mut rng_seed: u64) {
+        // Judge neighbours against the carved layout, not against alcoves opened in this pass
+        let layout = self.cells.clone();
+        
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                if !layout[y][x] || self.is_near_exit(x, y) {
+                    continue;
+                }
+                
+                let open_x = !layout[y][x - 1] && !layout[y][x + 1];
+                let open_y = !layout[y - 1][x] && !layout[y + 1][x];
+                let open_count = [layout[y][x - 1], layout[y][x + 1], layout[y - 1][x], layout[y + 1][x]]
+                    .iter()
+                    .filter(|&&is_wall| !is_wall)
+                    .count();
+                
+                rng_seed = rng_seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let roll = (rng_seed >> 16) % 100;
+                
+                if (open_x || open_y) && open_count == 2 && roll < 28 {
+                    // Wall between two corridors - let the player see into the next one
+                    if roll < 12 {
+                        self.heights[y][x] = HALF_WALL_HEIGHT;
+                    } else if roll < 16 {
+                        self.materials[y][x] = WallMaterial::Grate;
+                    } else if roll < 20 {
+                        self.materials[y][x] = WallMaterial::Glass;
+                    } else if roll < 24 {
+                        self.materials[y][x] = WallMaterial::Window;
+                    } else {
+                        let open = 0.3 + ((rng_seed >> 24) % 30) as f64 / 100.0;
+                        self.materials[y][x] = WallMaterial::Door { open };
+                    }
+                } else if open_count == 0 && roll < 15 {
+                    // Corner post - raise it into a pillar
+                    self.heights[y][x] = PILLAR_HEIGHT;
+                } else if open_count == 1 && roll < 25 && self.open_sides(x, y) == 1 {
+                    // Wall with a single open side - hollow it into an alcove,
+                    // unless an alcove next to it already opened a second side
+                    self.cells[y][x] = false;
+                }
+            }
+        }
+    }
+
+    // Number of a cell's four neighbours that are open as the cells are now
+    fn open_sides(&self, x: usize, y: usize) -> usize {
+        [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter().filter(|&&(x, y)| !self.cells[y][x]).count()
+    }
+    
+    fn is_near_exit(&self, x: usize, y: usize) -> bool {
+        x.abs_diff(self.exit.0) + y.abs_diff(self.exit.1) <= 1
     }
 
     fn get_unvisited_neighbors(&self, pos: (usize, usize), visited: &HashSet<(usize, usize)>) -> Vec<(usize, usize)> {
@@ -226,6 +363,46 @@ impl Maze {
         self.cells[y][x]
     }
 
+    /// Height of the wall in a cell, 0.0 for empty cells.
+    /// 
+    /// Cells without an explicit height are treated as full walls.
+    pub fn wall_height(&self, x: usize, y: usize) -> f64 {
+        if !self.is_wall(x, y) {
+            return 0.0;
+        }
+        self.heights
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or(FULL_WALL_HEIGHT)
+    }
+
+    /// Material of the wall in a cell. Cells without an explicit material,
+    /// including everything outside the maze, are solid.
+    pub fn material(&self, x: usize, y: usize) -> WallMaterial {
+        self.materials
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The tallest wall in the maze. A ray can stop once nothing this tall
+    /// could show above the walls it has hit.
+    pub fn max_wall_height(&self) -> f64 {
+        self.tallest_wall
+    }
+    
+    fn measure_tallest_wall(&self) -> f64 {
+        self.heights
+            .iter()
+            .zip(self.cells.iter())
+            .flat_map(|(heights, cells)| heights.iter().zip(cells.iter()))
+            .filter(|&(_, &is_wall)| is_wall)
+            .map(|(&h, _)| h)
+            .fold(FULL_WALL_HEIGHT, f64::max)
+    }
+
     pub fn get_cell(&self, x: f64, y: f64) -> bool {
         let ix = x as usize;
         let iy = y as usize;
@@ -234,3 +411,57 @@ impl Maze {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Loops in the open cells, and how many separate areas they fall into.
+    // A perfect maze has no loops and a single area.
+    fn loops_and_areas(cells: &[Vec<bool>]) -> (usize, usize) {
+        let open = |x: usize, y: usize| cells.get(y).and_then(|row| row.get(x)) == Some(&false);
+        let all = || (0..cells.len()).flat_map(|y| (0..cells[0].len()).map(move |x| (x, y))).filter(|&(x, y)| open(x, y));
+        let edges: usize = all().map(|(x, y)| usize::from(open(x + 1, y)) + usize::from(open(x, y + 1))).sum();
+        
+        let mut seen = vec![vec![false; cells[0].len()]; cells.len()];
+        let mut areas = 0;
+        for (x, y) in all() {
+            if seen[y][x] {
+                continue;
+            }
+            areas += 1;
+            seen[y][x] = true;
+            let mut stack = vec![(x, y)];
+            while let Some((x, y)) = stack.pop() {
+                for (nx, ny) in [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)] {
+                    if open(nx, ny) && !seen[ny][nx] {
+                        seen[ny][nx] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+        }
+        (edges + areas - all().count(), areas)
+    }
+    
+    #[test]
+    fn test_decorating_keeps_the_maze_a_tree() {
+        for seed in 0..200 {
+            let (width, height) = (21, 15);
+            let mut maze = Maze::from_layout(
+                vec![vec![true; width]; height],
+                vec![vec![FULL_WALL_HEIGHT; width]; height],
+                vec![vec![WallMaterial::Solid; width]; height],
+                (1, 1),
+                (0, 0),
+            );
+            let rng_seed = maze.carve(seed);
+            let (carved_loops, _) = loops_and_areas(&maze.cells);
+            maze.decorate_walls(rng_seed);
+            
+            // Alcoves only ever add dead ends; the exit opening can already
+            // join two corridors, but decorating never joins any more
+            assert_eq!(loops_and_areas(&maze.cells), (carved_loops, 1), "seed {}", seed);
+        }
+    }
+}
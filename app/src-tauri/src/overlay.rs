@@ -0,0 +1,318 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Fills the cell covered by the right half of a double-width character, so
+/// grid columns stay screen columns. It's skipped when a frame is written out.
+pub const WIDE_TAIL: char = '\0';
+
+/// Where on the frame a layer's block is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// How lines narrower than their block are placed in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+/// Characters a block is boxed in with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Border {
+    Single, // ┌─┐
+    Double, // ╔═╗
+    Ascii, // +-+
+}
+
+/// What happens to a line too wide for the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    #[default]
+    Clip, // Cut off at the edge
+    Ellipsis, // Cut short and ended with `…`
+}
+
+/// A block of text drawn over the frame.
+/// 
+/// Lines are measured in screen columns, not bytes or chars, so box-drawing
+/// art and double-width glyphs line up. A block too big for the frame loses
+/// its bottom lines and the ends of its widest ones.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Layer {
+    pub lines: Vec<String>,
+    pub anchor: Anchor,
+    pub align: Align,
+    pub margin: (usize, usize), // Rows and columns between the block and the frame edges it's anchored to
+    pub padding: (usize, usize), // Blank rows and columns round the text, inside the border
+    pub border: Option<Border>,
+    pub opaque: bool, // Blank the whole block first; otherwise only the lines' own text is drawn
+    pub overflow: Overflow,
+}
+
+/// Layers of text drawn over a frame in order, later ones on top.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Overlay {
+    pub layers: Vec<Layer>,
+}
+
+/// Number of screen columns `text` takes up.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+// Columns a character takes up; control and zero-width characters aren't drawn
+fn char_width(ch: char) -> usize {
+    ch.width().unwrap_or(0)
+}
+
+// The longest start of `text` that fits in `width` columns
+fn fit(text: &str, width: usize, overflow: Overflow) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let (limit, tail) = match overflow {
+        Overflow::Clip => (width, ""),
+        Overflow::Ellipsis => (width.saturating_sub(1), if width > 0 { "…" } else { "" }),
+    };
+    let mut used = 0;
+    let mut fitted: String = text
+        .chars()
+        .take_while(|&ch| {
+            used += char_width(ch);
+            used <= limit
+        })
+        .collect();
+    fitted.push_str(tail);
+    fitted
+}
+
+/// Writes `text` into a row from column `col`, cutting off whatever doesn't
+/// fit. A double-width character takes two cells, the second holding
+/// `WIDE_TAIL`, and one half overwritten clears the other.
+pub fn write(row: &mut [char], col: usize, text: &str) {
+    let mut col = col;
+    for ch in text.chars() {
+        let width = char_width(ch);
+        if width == 0 {
+            continue;
+        }
+        if col + width > row.len() {
+            break;
+        }
+        // Don't leave half of a wide character behind at either end
+        if row[col] == WIDE_TAIL && col > 0 {
+            row[col - 1] = ' ';
+        }
+        if let Some(next) = row.get_mut(col + width) {
+            if *next == WIDE_TAIL {
+                *next = ' ';
+            }
+        }
+        row[col] = ch;
+        if width == 2 {
+            row[col + 1] = WIDE_TAIL;
+        }
+        col += width;
+    }
+}
+
+impl Layer {
+    /// A centered, see-through block of `lines` with no border.
+    pub fn new<S: Into<String>>(lines: impl IntoIterator<Item = S>) -> Self {
+        Layer { lines: lines.into_iter().map(Into::into).collect(), ..Self::default() }
+    }
+    
+    /// Draws the block onto a grid of cells.
+    pub fn draw(&self, cells: &mut [Vec<char>]) {
+        let height = cells.len();
+        let width = cells.first().map_or(0, Vec::len);
+        let frame = usize::from(self.border.is_some());
+        let chrome = (self.padding.0 + frame, self.padding.1 + frame); // Rows and columns on each side of the text
+        
+        // Size the block to its text, then shrink it to the room there is
+        let (left_anchored, right_anchored) = match self.anchor {
+            Anchor::TopLeft | Anchor::BottomLeft => (true, false),
+            Anchor::TopRight | Anchor::BottomRight => (false, true),
+            _ => (false, false),
+        };
+        let (top_anchored, bottom_anchored) = match self.anchor {
+            Anchor::Top | Anchor::TopLeft | Anchor::TopRight => (true, false),
+            Anchor::Bottom | Anchor::BottomLeft | Anchor::BottomRight => (false, true),
+            Anchor::Center => (false, false),
+        };
+        let room_width = width.saturating_sub(self.margin.1 * if left_anchored || right_anchored { 1 } else { 2 });
+        let room_height = height.saturating_sub(self.margin.0 * if top_anchored || bottom_anchored { 1 } else { 2 });
+        let text_width = self.lines.iter().map(|line| display_width(line)).max().unwrap_or(0);
+        let box_width = (text_width + 2 * chrome.1).min(room_width);
+        let box_height = (self.lines.len() + 2 * chrome.0).min(room_height);
+        let text_width = box_width.saturating_sub(2 * chrome.1);
+        let text_height = box_height.saturating_sub(2 * chrome.0);
+        
+        let left = if left_anchored {
+            self.margin.1
+        } else if right_anchored {
+            width.saturating_sub(self.margin.1 + box_width)
+        } else {
+            (width - box_width) / 2
+        };
+        let top = if top_anchored {
+            self.margin.0
+        } else if bottom_anchored {
+            height.saturating_sub(self.margin.0 + box_height)
+        } else {
+            // Odd space goes above, so a single line sits on row `height / 2`
+            (height - box_height).div_ceil(2)
+        };
+        // Margins wider than the frame leave an empty block at its edge
+        let (top, left) = (top.min(height - box_height), left.min(width - box_width));
+        
+        if self.opaque {
+            for row in &mut cells[top..top + box_height] {
+                write(row, left, &" ".repeat(box_width));
+            }
+        }
+        if let Some(border) = self.border {
+            self.draw_border(cells, border, (top, left), (box_height, box_width));
+        }
+        for (i, line) in self.lines.iter().take(text_height).enumerate() {
+            let line = fit(line, text_width, self.overflow);
+            let slack = text_width - display_width(&line);
+            let indent = match self.align {
+                Align::Left => 0,
+                Align::Center => slack / 2,
+                Align::Right => slack,
+            };
+            write(&mut cells[top + chrome.0 + i], left + chrome.1 + indent, &line);
+        }
+    }
+    
+    fn draw_border(&self, cells: &mut [Vec<char>], border: Border, (top, left): (usize, usize), (height, width): (usize, usize)) {
+        if height < 2 || width < 2 {
+            return;
+        }
+        let [top_left, top_right, bottom_left, bottom_right, across, down] = match border {
+            Border::Single => ['┌', '┐', '└', '┘', '─', '│'],
+            Border::Double => ['╔', '╗', '╚', '╝', '═', '║'],
+            Border::Ascii => ['+', '+', '+', '+', '-', '|'],
+        };
+        let edge = across.to_string().repeat(width - 2);
+        write(&mut cells[top], left, &format!("{}{}{}", top_left, edge, top_right));
+        write(&mut cells[top + height - 1], left, &format!("{}{}{}", bottom_left, edge, bottom_right));
+        for row in &mut cells[top + 1..top + height - 1] {
+            write(row, left, &down.to_string());
+            write(row, left + width - 1, &down.to_string());
+        }
+    }
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn push(&mut self, layer: Layer) {
+        self.layers.push(layer);
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+    
+    pub fn draw(&self, cells: &mut [Vec<char>]) {
+        for layer in &self.layers {
+            layer.draw(cells);
+        }
+    }
+    
+    /// Draws the overlay onto a frame that's already been written out as
+    /// text, one line per row.
+    pub fn apply(&self, frame: &str) -> String {
+        let mut cells: Vec<Vec<char>> = frame
+            .split('\n')
+            .map(|line| {
+                let mut row = Vec::new();
+                for ch in line.chars() {
+                    row.push(ch);
+                    if char_width(ch) == 2 {
+                        row.push(WIDE_TAIL);
+                    }
+                }
+                row
+            })
+            .collect();
+        // Rows all need the same width for the layers to line up
+        let width = cells.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut cells {
+            row.resize(width, ' ');
+        }
+        self.draw(&mut cells);
+        cells
+            .iter()
+            .map(|row| row.iter().filter(|&&ch| ch != WIDE_TAIL).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn blank(width: usize, height: usize) -> String {
+        vec![".".repeat(width); height].join("\n")
+    }
+    
+    #[test]
+    fn test_layers_are_placed_by_display_width() {
+        // Box-drawing art is 3 bytes a character but one column wide
+        let mut overlay = Overlay::new();
+        overlay.push(Layer::new(["██╗", "╚═╝"]));
+        overlay.push(Layer { anchor: Anchor::TopRight, margin: (0, 1), ..Layer::new(["ab"]) });
+        overlay.push(Layer { anchor: Anchor::BottomLeft, align: Align::Right, ..Layer::new(["x", "yz"]) });
+        assert_eq!(overlay.apply(&blank(9, 5)), "......ab.\n.........\n...██╗...\n.x.╚═╝...\nyz.......");
+        // A centered line sits on the middle row, or the lower of the two
+        let frame = Overlay { layers: vec![Layer::new(["mid"])] }.apply(&blank(5, 4));
+        assert_eq!(frame, ".....\n.....\n.mid.\n.....");
+    }
+    
+    #[test]
+    fn test_wide_characters_take_two_columns() {
+        assert_eq!(display_width("ｱﾐ"), 2); // Half-width katakana, as the glyph ramp uses
+        assert_eq!(display_width("迷路"), 4);
+        let frame = Overlay { layers: vec![Layer::new(["迷路"])] }.apply(&blank(8, 1));
+        assert_eq!(frame, "..迷路..");
+        // Writing over half of a wide character clears the other half
+        let frame = Overlay { layers: vec![Layer { anchor: Anchor::TopLeft, margin: (0, 3), ..Layer::new(["x"]) }] }.apply(&frame);
+        assert_eq!(frame, ".. x路..");
+    }
+    
+    #[test]
+    fn test_borders_padding_and_truncation() {
+        let boxed = Layer { border: Some(Border::Ascii), padding: (0, 1), opaque: true, ..Layer::new(["hi"]) };
+        assert_eq!(Overlay { layers: vec![boxed] }.apply(&blank(8, 3)), ".+----+.\n.| hi |.\n.+----+.");
+        
+        let long = Layer { overflow: Overflow::Ellipsis, ..Layer::new(["much too long", "ok", "dropped"]) };
+        assert_eq!(Overlay { layers: vec![long] }.apply(&blank(6, 2)), "much …\n..ok..");
+    }
+    
+    #[test]
+    fn test_margins_wider_than_the_frame() {
+        // Like the ghost's splits and the minimap in a one-column window
+        let anchors = [Anchor::TopLeft, Anchor::TopRight, Anchor::BottomLeft, Anchor::BottomRight, Anchor::Top, Anchor::Bottom];
+        let layers = anchors
+            .iter()
+            .map(|&anchor| Layer { anchor, margin: (1, 2), border: Some(Border::Single), opaque: true, ..Layer::new(["split"]) })
+            .collect();
+        assert_eq!(Overlay { layers }.apply(&blank(1, 1)), ".");
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Whether rendering work may be split across threads.
+/// 
+/// With the `parallel` cargo feature enabled, work is spread over rayon's
+/// work-stealing thread pool. Without it everything runs on the calling
+/// thread and `Parallel` behaves like `Single`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threading {
+    Single,
+    Parallel,
+}
+
+impl Default for Threading {
+    fn default() -> Self {
+        if cfg!(feature = "parallel") {
+            Threading::Parallel
+        } else {
+            Threading::Single
+        }
+    }
+}
+
+/// Computes `f(i)` for every `i` in `0..len`, in order.
+pub fn map_range<T, F>(len: usize, threading: Threading, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if threading == Threading::Parallel {
+        return (0..len).into_par_iter().map(f).collect();
+    }
+    
+    let _ = threading;
+    (0..len).map(f).collect()
+}
+
+/// Calls `f(index, item)` for every item, possibly from several threads.
+pub fn for_each_indexed<T, F>(items: &mut [T], threading: Threading, f: F)
+where
+    T: Send,
+    F: Fn(usize, &mut T) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if threading == Threading::Parallel {
+        items.par_iter_mut().enumerate().for_each(|(i, item)| f(i, item));
+        return;
+    }
+    
+    let _ = threading;
+    items.iter_mut().enumerate().for_each(|(i, item)| f(i, item));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parallel_matches_single() {
+        let single = map_range(1000, Threading::Single, |i| i * i);
+        let parallel = map_range(1000, Threading::Parallel, |i| i * i);
+        assert_eq!(single, parallel);
+        
+        let mut rows_single = vec![0; 500];
+        let mut rows_parallel = vec![0; 500];
+        for_each_indexed(&mut rows_single, Threading::Single, |i, row| *row = i * 3);
+        for_each_indexed(&mut rows_parallel, Threading::Parallel, |i, row| *row = i * 3);
+        assert_eq!(rows_single, rows_parallel);
+    }
+}
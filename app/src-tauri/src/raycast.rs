@@ -1,23 +1,96 @@
+use crate::dither::{DitherConfig, Sample, UvMapping};
+use crate::glyphs::GlyphRamp;
+use crate::maze::{Maze, WallMaterial, FULL_WALL_HEIGHT};
+use crate::thin_maze::{ThinMaze, WALL_THICKNESS};
+
+// Height of the camera, in standard walls; `wall_span` centres the view on it
+const EYE_LEVEL: f64 = 0.5;
+
+/// A wall cell crossed by a ray.
+pub struct WallHit {
+    pub distance: f64,
+    pub wall_type: u8, // 0-3 for N, S, E, W walls
+    pub wall_height: f64, // In multiples of a standard wall
+    pub material: WallMaterial,
+    pub hit_x: f64,
+    pub hit_y: f64,
+    pub cell: (i32, i32), // May lie outside the maze for the boundary hit
+}
+
+impl WallHit {
+    /// Horizontal position across the wall face, in [0.0, 1.0).
+    pub fn wall_u(&self) -> f64 {
+        along_wall(self.wall_type, self.hit_x, self.hit_y).rem_euclid(1.0)
+    }
+
+    /// True if this hit hides everything behind it that's no taller than
+    /// a standard wall.
+    fn is_occluding(&self) -> bool {
+        !self.material.is_see_through() && self.wall_height >= FULL_WALL_HEIGHT
+    }
+    
+    // How steeply the top of the wall rises above eye level, seen from where
+    // the ray started; of two walls, the one with the steeper top shows higher
+    fn top_slope(&self) -> f64 {
+        (self.wall_height - EYE_LEVEL) / self.distance
+    }
+}
+
 pub struct RaycastResult {
     pub distance: f64,
     pub wall_type: u8, // 0-3 for N, S, E, W walls
+    pub wall_height: f64, // Height of the wall that stopped the ray
     #[allow(dead_code)]
     pub hit_x: f64,
     #[allow(dead_code)]
     pub hit_y: f64,
     pub passed_exit: bool, // True if ray passed through the exit
     pub exit_threshold_dist: Option<f64>, // Distance to exit threshold if ray would hit it
+    pub hits: Vec<WallHit>, // Shorter or see-through walls in front of the final one, nearest first
+    pub cells: Vec<(i32, i32)>, // Grid cells the ray crossed, nearest first, up to the first wall that hides the floor; empty for thin-wall mazes
 }
 
-pub fn cast_ray(
+/// Casts a ray and returns every wall cell it crosses, nearest first.
+/// 
+/// Unlike a plain first-hit raycast, the ray keeps going through walls that
+/// can be seen past: see-through materials (grates, glass, windows, open
+/// doors) and walls shorter than a standard wall. An opaque wall at least
+/// standard height hides the rest, except where taller walls further on
+/// show above it; the ray goes on looking for those until none could, at
+/// the edge of the maze, or at `max_distance`, whichever comes first.
+/// 
+/// # Arguments
+/// 
+/// * `start_x` - World-space X coordinate of the ray origin
+/// * `start_y` - World-space Y coordinate of the ray origin
+/// * `angle` - Ray direction in radians
+/// * `maze` - The maze to cast through
+/// * `max_distance` - Hits at or beyond this distance are dropped
+/// 
+/// # Returns
+/// 
+/// The hits in order of increasing distance. When the ray leaves the maze the
+/// last hit is the boundary cell just outside it.
+pub fn cast_ray_all(
     start_x: f64,
     start_y: f64,
     angle: f64,
-    maze: &crate::maze::Maze,
+    maze: &Maze,
     max_distance: f64,
-    exit_x: Option<f64>,
-    exit_y: Option<f64>,
-) -> RaycastResult {
+) -> Vec<WallHit> {
+    trace(start_x, start_y, angle, maze, max_distance).0
+}
+
+// The DDA behind `cast_ray_all`: the wall hits, and every grid cell the ray
+// crossed up to the first occluding wall, starting with the one it was cast
+// from
+fn trace(
+    start_x: f64,
+    start_y: f64,
+    angle: f64,
+    maze: &Maze,
+    max_distance: f64,
+) -> (Vec<WallHit>, Vec<(i32, i32)>) {
     let dx = angle.cos();
     let dy = angle.sin();
     
@@ -49,148 +122,369 @@ pub fn cast_ray(
         side_dist_y = (map_y as f64 + 1.0 - start_y) * delta_dist_y;
     }
     
-    let mut hit = false;
-    let mut side = 0; // 0 = x-side, 1 = y-side
-    let mut passed_exit = false;
-    let mut exit_threshold_dist: Option<f64> = None;
-    
-    // Calculate exit cell coordinates
-    let exit_cell_x = exit_x.map(|x| x as i32);
-    let exit_cell_y = exit_y.map(|y| y as i32);
+    // Past an occluding wall only walls whose tops show above the highest
+    // one so far can be seen, and none can once even the tallest wall in the
+    // maze would be hidden
+    let tallest = maze.max_wall_height();
+    let mut horizon: Option<f64> = None;
+    let mut hits: Vec<WallHit> = Vec::new();
+    let mut cells = vec![(map_x, map_y)];
     
-    while !hit {
-        let prev_map_x = map_x;
-        let prev_map_y = map_y;
-        let prev_side_dist_x = side_dist_x;
-        let prev_side_dist_y = side_dist_y;
-        
-        if side_dist_x < side_dist_y {
+    loop {
+        // 0 = x-side, 1 = y-side
+        let side = if side_dist_x < side_dist_y {
             side_dist_x += delta_dist_x;
             map_x += step_x;
-            side = 0;
+            0
         } else {
             side_dist_y += delta_dist_y;
             map_y += step_y;
-            side = 1;
+            1
+        };
+        
+        let distance = if side == 0 {
+            side_dist_x - delta_dist_x
+        } else {
+            side_dist_y - delta_dist_y
+        };
+        if distance >= max_distance {
+            break;
+        }
+        if let Some(horizon) = horizon {
+            if (tallest - EYE_LEVEL) / distance <= horizon {
+                break;
+            }
+        } else {
+            cells.push((map_x, map_y));
+        }
+        
+        let out_of_bounds = map_x < 0 || map_y < 0 || map_x as usize >= maze.width || map_y as usize >= maze.height;
+        if !out_of_bounds && !maze.is_wall(map_x as usize, map_y as usize) {
+            continue;
         }
         
-        // Check if we're about to enter the exit cell - calculate threshold distance
-        if let (Some(ex_x), Some(ex_y)) = (exit_x, exit_y) {
-            if prev_map_x != ex_x as i32 || prev_map_y != ex_y as i32 {
-                if map_x == ex_x as i32 && map_y == ex_y as i32 {
-                    // Calculate distance to the actual exit position (center of exit cell)
-                    // This makes the floor continue to the actual exit opening
-                    let exit_world_x = ex_x;
-                    let exit_world_y = ex_y;
-                    
-                    // Calculate distance along the ray to the exit position
-                    // Solve: start_x + t*dx = exit_world_x, start_y + t*dy = exit_world_y
-                    let threshold_dist = if dx.abs() > dy.abs() {
-                        (exit_world_x - start_x) / dx
-                    } else if dy != 0.0 {
-                        (exit_world_y - start_y) / dy
-                    } else {
-                        // Fallback to side distance
-                        if side == 0 {
-                            side_dist_x - delta_dist_x
-                        } else {
-                            side_dist_y - delta_dist_y
-                        }
-                    };
-                    
-                    if threshold_dist > 0.0 {
-                        exit_threshold_dist = Some(threshold_dist);
-                        passed_exit = true;
-                    }
+        let (wall_height, material) = if out_of_bounds {
+            (FULL_WALL_HEIGHT, WallMaterial::Solid)
+        } else {
+            (
+                maze.wall_height(map_x as usize, map_y as usize),
+                maze.material(map_x as usize, map_y as usize),
+            )
+        };
+        let hit = WallHit {
+            distance,
+            wall_type: wall_type_for(side, step_x, step_y),
+            wall_height,
+            material,
+            hit_x: start_x + dx * distance,
+            hit_y: start_y + dy * distance,
+            cell: (map_x, map_y),
+        };
+        if horizon.is_some_and(|horizon| hit.top_slope() <= horizon) {
+            continue;
+        }
+        let stop = out_of_bounds || (hit.is_occluding() && hit.wall_height >= tallest);
+        if hit.is_occluding() {
+            horizon = Some(horizon.map_or(hit.top_slope(), |horizon| horizon.max(hit.top_slope())));
+        }
+        hits.push(hit);
+        if stop {
+            break;
+        }
+    }
+    
+    (hits, cells)
+}
+
+pub fn cast_ray(
+    start_x: f64,
+    start_y: f64,
+    angle: f64,
+    maze: &Maze,
+    max_distance: f64,
+    exit_x: Option<f64>,
+    exit_y: Option<f64>,
+) -> RaycastResult {
+    let dx = angle.cos();
+    let dy = angle.sin();
+    
+    let (mut hits, cells) = trace(start_x, start_y, angle, maze, max_distance);
+    
+    // The last hit is the final wall if it hides the floor behind it
+    let final_hit = match hits.last() {
+        Some(last) if last.is_occluding() || !maze_contains(maze, last.cell) => hits.pop(),
+        _ => None,
+    };
+    
+    // The exit only counts if the ray reaches it before a full-height solid wall
+    let blocked_at = hits
+        .iter()
+        .chain(final_hit.iter())
+        .find(|hit| hit.is_occluding())
+        .map_or(max_distance, |hit| hit.distance);
+    
+    let mut passed_exit = false;
+    let mut exit_threshold_dist: Option<f64> = None;
+    
+    if let (Some(ex_x), Some(ex_y)) = (exit_x, exit_y) {
+        if let Some(entry_dist) = cell_entry_distance(start_x, start_y, dx, dy, ex_x as i32, ex_y as i32) {
+            if entry_dist < blocked_at {
+                // Calculate distance along the ray to the actual exit position (center of exit cell)
+                // This makes the floor continue to the actual exit opening
+                // Solve: start_x + t*dx = exit_world_x, start_y + t*dy = exit_world_y
+                let threshold_dist = if dx.abs() > dy.abs() {
+                    (ex_x - start_x) / dx
+                } else if dy != 0.0 {
+                    (ex_y - start_y) / dy
+                } else {
+                    // Fallback to the distance where the ray enters the exit cell
+                    entry_dist
+                };
+                
+                if threshold_dist > 0.0 {
+                    exit_threshold_dist = Some(threshold_dist);
+                    passed_exit = true;
                 }
             }
         }
-        
+    }
+    
+    match final_hit {
         // If we passed the exit and go out of bounds, show open sky
-        if passed_exit && (map_x < 0 || map_y < 0 || map_x as usize >= maze.width || map_y as usize >= maze.height) {
-            // Return max_distance to show open space (ceiling), but use exit threshold for wall rendering
+        Some(hit) if passed_exit && !maze_contains(maze, hit.cell) => {
+            // Use the exit threshold as the distance for wall rendering
             let final_distance = exit_threshold_dist.unwrap_or(max_distance);
-            return RaycastResult {
+            hits.retain(|hit| hit.distance < final_distance);
+            RaycastResult {
                 distance: final_distance,
                 wall_type: 0,
+                wall_height: FULL_WALL_HEIGHT,
                 hit_x: start_x + dx * final_distance,
                 hit_y: start_y + dy * final_distance,
                 passed_exit: true,
                 exit_threshold_dist,
-            };
-        }
-        
-        if map_x < 0 || map_y < 0 || map_x as usize >= maze.width || map_y as usize >= maze.height {
-            break;
+                hits,
+                cells,
+            }
         }
-        
-        // Don't treat exit cell as a wall
-        let is_exit = if let (Some(ex_x), Some(ex_y)) = (exit_cell_x, exit_cell_y) {
-            map_x == ex_x && map_y == ex_y
+        Some(hit) => RaycastResult {
+            distance: hit.distance,
+            wall_type: hit.wall_type,
+            wall_height: hit.wall_height,
+            hit_x: hit.hit_x,
+            hit_y: hit.hit_y,
+            passed_exit,
+            exit_threshold_dist,
+            hits,
+            cells,
+        },
+        // Nothing solid within range - fade out at max distance
+        None => RaycastResult {
+            distance: max_distance,
+            wall_type: 0,
+            wall_height: FULL_WALL_HEIGHT,
+            hit_x: start_x + dx * max_distance,
+            hit_y: start_y + dy * max_distance,
+            passed_exit,
+            exit_threshold_dist,
+            hits,
+            cells,
+        },
+    }
+}
+
+/// Casts a ray through an edge-based `ThinMaze`, stopping at the first wall
+/// segment it crosses.
+/// 
+/// Walls have `WALL_THICKNESS`, so the visible face sits half a thickness in
+/// front of the cell edge. A ray that leaves through the exit opening reports
+/// `passed_exit` with the opening as its threshold.
+pub fn cast_ray_thin(
+    start_x: f64,
+    start_y: f64,
+    angle: f64,
+    maze: &ThinMaze,
+    max_distance: f64,
+) -> RaycastResult {
+    let dx = angle.cos();
+    let dy = angle.sin();
+    
+    // DDA over cells, checking the edge crossed on every step
+    let mut map_x = start_x.floor() as i32;
+    let mut map_y = start_y.floor() as i32;
+    
+    let delta_dist_x = if dx == 0.0 { 1e30 } else { (1.0 / dx).abs() };
+    let delta_dist_y = if dy == 0.0 { 1e30 } else { (1.0 / dy).abs() };
+    
+    let (step_x, mut side_dist_x) = if dx < 0.0 {
+        (-1, (start_x - map_x as f64) * delta_dist_x)
+    } else {
+        (1, (map_x as f64 + 1.0 - start_x) * delta_dist_x)
+    };
+    let (step_y, mut side_dist_y) = if dy < 0.0 {
+        (-1, (start_y - map_y as f64) * delta_dist_y)
+    } else {
+        (1, (map_y as f64 + 1.0 - start_y) * delta_dist_y)
+    };
+    
+    let half_thickness = WALL_THICKNESS / 2.0;
+    
+    loop {
+        let (side, edge_dist, blocked) = if side_dist_x < side_dist_y {
+            // Crossing a vertical edge - the left edge of the cell on its right
+            let edge_x = if step_x > 0 { map_x + 1 } else { map_x };
+            let blocked = maze.has_west_wall(edge_x, map_y);
+            let dist = side_dist_x;
+            side_dist_x += delta_dist_x;
+            map_x += step_x;
+            (0, dist - half_thickness * delta_dist_x, blocked)
         } else {
-            false
+            // Crossing a horizontal edge - the top edge of the cell below it
+            let edge_y = if step_y > 0 { map_y + 1 } else { map_y };
+            let blocked = maze.has_north_wall(map_x, edge_y);
+            let dist = side_dist_y;
+            side_dist_y += delta_dist_y;
+            map_y += step_y;
+            (1, dist - half_thickness * delta_dist_y, blocked)
         };
         
-        if !is_exit && maze.is_wall(map_x as usize, map_y as usize) {
-            hit = true;
+        let out_of_bounds = map_x < 0 || map_y < 0 || map_x as usize >= maze.width || map_y as usize >= maze.height;
+        
+        if out_of_bounds && !blocked {
+            // Left through the exit opening - show open sky beyond it
+            let threshold_dist = edge_dist + half_thickness * if side == 0 { delta_dist_x } else { delta_dist_y };
+            return RaycastResult {
+                distance: threshold_dist,
+                wall_type: 0,
+                wall_height: FULL_WALL_HEIGHT,
+                hit_x: start_x + dx * threshold_dist,
+                hit_y: start_y + dy * threshold_dist,
+                passed_exit: true,
+                exit_threshold_dist: Some(threshold_dist),
+                hits: Vec::new(),
+                cells: Vec::new(),
+            };
+        }
+        
+        if blocked || edge_dist >= max_distance {
+            let distance = edge_dist.clamp(0.0, max_distance);
+            return RaycastResult {
+                distance,
+                wall_type: wall_type_for(side, step_x, step_y),
+                wall_height: FULL_WALL_HEIGHT,
+                hit_x: start_x + dx * distance,
+                hit_y: start_y + dy * distance,
+                passed_exit: false,
+                exit_threshold_dist: None,
+                hits: Vec::new(),
+                cells: Vec::new(),
+            };
         }
     }
-    
-    let perp_wall_dist = if side == 0 {
-        side_dist_x - delta_dist_x
-    } else {
-        side_dist_y - delta_dist_y
+}
+
+fn maze_contains(maze: &Maze, cell: (i32, i32)) -> bool {
+    cell.0 >= 0 && cell.1 >= 0 && (cell.0 as usize) < maze.width && (cell.1 as usize) < maze.height
+}
+
+/// Distance along a ray at which it enters the given grid cell, if it does
+/// so ahead of the origin.
+fn cell_entry_distance(start_x: f64, start_y: f64, dx: f64, dy: f64, cell_x: i32, cell_y: i32) -> Option<f64> {
+    // Slab test against the cell's bounding box
+    let slab = |start: f64, dir: f64, min: f64| -> (f64, f64) {
+        if dir == 0.0 {
+            if start >= min && start < min + 1.0 {
+                (f64::NEG_INFINITY, f64::INFINITY)
+            } else {
+                (f64::INFINITY, f64::NEG_INFINITY)
+            }
+        } else {
+            let t0 = (min - start) / dir;
+            let t1 = (min + 1.0 - start) / dir;
+            (t0.min(t1), t0.max(t1))
+        }
     };
     
-    let distance = perp_wall_dist.min(max_distance);
-    let hit_x = start_x + dx * distance;
-    let hit_y = start_y + dy * distance;
+    let (near_x, far_x) = slab(start_x, dx, cell_x as f64);
+    let (near_y, far_y) = slab(start_y, dy, cell_y as f64);
+    let entry = near_x.max(near_y);
+    let exit = far_x.min(far_y);
     
-    // Determine wall type based on which side was hit
-    let wall_type = if side == 0 {
+    if entry < exit && entry > 0.0 {
+        Some(entry)
+    } else {
+        None
+    }
+}
+
+// Determine wall type based on which side was hit
+fn wall_type_for(side: i32, step_x: i32, step_y: i32) -> u8 {
+    if side == 0 {
         if step_x > 0 { 3 } else { 2 } // East or West
     } else {
         if step_y > 0 { 1 } else { 0 } // South or North
+    }
+}
+
+/// Screen rows covered by a wall of the given height at the given distance.
+/// 
+/// The eye sits at half a standard wall, so every wall shares the same bottom
+/// edge for a given distance and taller walls extend further up.
+/// 
+/// # Returns
+/// 
+/// A (start, end) row range, clamped to the screen
+pub fn wall_span(distance: f64, wall_height: f64, height: usize) -> (usize, usize) {
+    let screen_height = height as f64;
+    let projected = projected_height(distance, height);
+    
+    let top = (screen_height - projected) / 2.0 - projected * (wall_height - 1.0);
+    let start = top.max(0.0) as usize;
+    let end = if top >= 0.0 {
+        start + (projected * wall_height) as usize
+    } else {
+        (top + projected * wall_height) as usize
     };
     
-    RaycastResult {
-        distance,
-        wall_type,
-        hit_x,
-        hit_y,
-        passed_exit,
-        exit_threshold_dist,
+    (start, end.min(height))
+}
+
+/// Height above the floor, in standard walls, of the wall point seen at `row`.
+/// 
+/// Unlike a position within `wall_span`, this doesn't shift when the wall is
+/// cut off by the edge of the screen, so it stays fixed to the wall.
+pub fn height_above_floor(distance: f64, row: usize, height: usize) -> f64 {
+    let projected = projected_height(distance, height);
+    let bottom = (height as f64 + projected) / 2.0;
+    (bottom - (row as f64 + 0.5)) / projected
+}
+
+/// World-space position along a wall face: the hit coordinate that varies
+/// across the face, which is `hit_y` for east and west walls.
+pub fn along_wall(wall_type: u8, hit_x: f64, hit_y: f64) -> f64 {
+    if wall_type >= 2 {
+        hit_y
+    } else {
+        hit_x
     }
 }
 
+// Screen rows covered by one standard wall height at the given distance
+fn projected_height(distance: f64, height: usize) -> f64 {
+    let screen_height = height as f64;
+    if distance > 0.01 {
+        (screen_height / distance).min(screen_height * 2.0)
+    } else {
+        screen_height * 2.0
+    }
+}
 
-pub fn get_ascii_char(distance: f64, wall_type: u8, max_distance: f64) -> char {
+
+pub fn get_ascii_char(distance: f64, max_distance: f64, ramp: &GlyphRamp) -> char {
     let normalized_dist = (distance / max_distance).min(1.0);
     
-    // Choose character based on distance and wall type
-    if normalized_dist < 0.1 {
-        match wall_type {
-            0 => '█', // North
-            1 => '█', // South
-            2 => '█', // West
-            3 => '█', // East
-            _ => '█',
-        }
-    } else if normalized_dist < 0.3 {
-        match wall_type {
-            0 => '▓',
-            1 => '▓',
-            2 => '▓',
-            3 => '▓',
-            _ => '▓',
-        }
-    } else if normalized_dist < 0.5 {
-        '▒'
-    } else if normalized_dist < 0.7 {
-        '░'
-    } else {
-        '·'
-    }
+    // Choose character based on distance: closer = denser glyph
+    ramp.glyph(1.0 - normalized_dist)
 }
 
 /// Gets an ASCII character using fractal dithering for more sophisticated rendering.
@@ -203,22 +497,22 @@ pub fn get_ascii_char(distance: f64, wall_type: u8, max_distance: f64) -> char {
 /// # Arguments
 /// 
 /// * `distance` - Distance to the wall
-/// * `wall_type` - Type of wall (0-3 for N, S, E, W)
 /// * `max_distance` - Maximum render distance
 /// * `hit_x` - World-space X coordinate of hit point
 /// * `hit_y` - World-space Y coordinate of hit point
 /// * `dither` - Reference to the dither pattern
+/// * `ramp` - Glyphs to shade with
 /// 
 /// # Returns
 /// 
-/// An ASCII character selected based on dithered brightness
+/// A character from the ramp selected based on dithered brightness
 pub fn get_dithered_ascii_char(
     distance: f64,
-    wall_type: u8,
     max_distance: f64,
     hit_x: f64,
     hit_y: f64,
     dither: &crate::dither::DitherPattern,
+    ramp: &GlyphRamp,
 ) -> char {
     let normalized_dist = (distance / max_distance).min(1.0);
     
@@ -232,31 +526,8 @@ pub fn get_dithered_ascii_char(
     // Apply fractal dithering
     let dithered_brightness = dither.dither(normalized_dist, uv, brightness);
     
-    // Map dithered brightness to ASCII character
-    // We use a smooth mapping instead of hard thresholds
-    if dithered_brightness > 0.85 {
-        match wall_type {
-            0 => '█', // North
-            1 => '█', // South
-            2 => '█', // West
-            3 => '█', // East
-            _ => '█',
-        }
-    } else if dithered_brightness > 0.65 {
-        match wall_type {
-            0 => '▓',
-            1 => '▓',
-            2 => '▓',
-            3 => '▓',
-            _ => '▓',
-        }
-    } else if dithered_brightness > 0.45 {
-        '▒'
-    } else if dithered_brightness > 0.25 {
-        '░'
-    } else {
-        '·'
-    }
+    // Map dithered brightness to a glyph from the ramp
+    ramp.glyph(dithered_brightness)
 }
 
 /// Gets an ASCII character using fractal dithering with per-pixel row position.
@@ -267,62 +538,122 @@ pub fn get_dithered_ascii_char(
 /// # Arguments
 /// 
 /// * `distance` - Distance to the wall
-/// * `wall_type` - Type of wall (0-3 for N, S, E, W)
 /// * `max_distance` - Maximum render distance
 /// * `hit_x` - World-space X coordinate of hit point
 /// * `hit_y` - World-space Y coordinate of hit point
 /// * `row` - Screen row position (for vertical pattern variation)
 /// * `dither` - Reference to the dither pattern
+/// * `ramp` - Glyphs to shade with
 /// 
 /// # Returns
 /// 
-/// An ASCII character selected based on dithered brightness
+/// A character from the ramp selected based on dithered brightness
 pub fn get_dithered_ascii_char_with_row(
     distance: f64,
-    wall_type: u8,
     max_distance: f64,
     hit_x: f64,
     hit_y: f64,
     row: f64,
     dither: &crate::dither::DitherPattern,
+    ramp: &GlyphRamp,
 ) -> char {
-    let normalized_dist = (distance / max_distance).min(1.0);
+    let config = DitherConfig { uv_mapping: UvMapping::WorldRow, ..DitherConfig::default() };
+    let sample = wall_sample(distance, max_distance, hit_x, hit_y, (0.0, 0.0), (0.0, row), &config);
     
-    // Calculate brightness: closer = brighter (inverse of distance)
-    let brightness = 1.0 - normalized_dist;
+    // Apply fractal dithering
+    let dithered_brightness = dither.dither(sample.normalized_dist, sample.uv, sample.brightness);
     
-    // Use hit position + row position as UV coordinates for pattern sampling
-    // This creates both horizontal and vertical variation
-    // Scale coordinates to get good pattern variation
-    let uv = (hit_x * 2.0, hit_y * 2.0 + row * 0.1);
+    // Map dithered brightness to a glyph from the ramp
+    ramp.glyph(dithered_brightness)
+}
+
+/// Shades one wall pixel, ready to be dithered.
+/// 
+/// # Arguments
+/// 
+/// * `distance` - Distance to the wall
+/// * `max_distance` - Maximum render distance
+/// * `hit_x` - World-space X coordinate of hit point
+/// * `hit_y` - World-space Y coordinate of hit point
+/// * `surface` - Position along the wall face and height above the floor
+/// * `screen` - Screen column and row of the pixel
+/// * `config` - Contrast and UV mapping to apply
+pub fn wall_sample(
+    distance: f64,
+    max_distance: f64,
+    hit_x: f64,
+    hit_y: f64,
+    surface: (f64, f64),
+    screen: (f64, f64),
+    config: &DitherConfig,
+) -> Sample {
+    let normalized_dist = (distance / max_distance).min(1.0);
     
-    // Apply fractal dithering
-    let dithered_brightness = dither.dither(normalized_dist, uv, brightness);
+    Sample {
+        // Calculate brightness: closer = brighter (inverse of distance)
+        brightness: config.apply_contrast(1.0 - normalized_dist),
+        normalized_dist,
+        // By default the point on the wall face, so the pattern moves with the
+        // wall instead of shimmering as the camera turns
+        uv: config.uv((hit_x, hit_y), surface, screen),
+    }
+}
+
+/// What one pixel of a wall shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallPixel {
+    Shaded(Sample), // Solid surface, to be dithered
+    Glyph(char), // Fixed detail such as a grate bar or a glint on glass
+}
+
+/// Gets one pixel of a wall hit, honouring its material.
+/// 
+/// Solid walls are shaded all over. See-through materials only draw a sparse
+/// overlay - bars, frame, glints - and return `None` where the pixel should
+/// show whatever lies behind the wall.
+/// 
+/// # Arguments
+/// 
+/// * `hit` - The wall hit being drawn
+/// * `v` - Vertical position on the wall face, 0.0 at the top and 1.0 at the bottom
+/// * `up` - Height of the pixel above the floor, for surface-mapped dithering
+/// * `screen` - Screen column and row of the pixel
+/// * `max_distance` - Maximum render distance
+/// * `config` - Contrast and UV mapping for shaded pixels
+pub fn get_wall_pixel(hit: &WallHit, v: f64, up: f64, screen: (f64, f64), max_distance: f64, config: &DitherConfig) -> Option<WallPixel> {
+    let u = hit.wall_u();
+    let surface = (along_wall(hit.wall_type, hit.hit_x, hit.hit_y), up);
+    let solid = || WallPixel::Shaded(wall_sample(hit.distance, max_distance, hit.hit_x, hit.hit_y, surface, screen, config));
+    // Faint diagonal streaks, fixed to the pane so they don't crawl while moving
+    let glint = || if (u * 3.0 + v * 2.0).rem_euclid(1.0) < 0.08 { Some(WallPixel::Glyph('/')) } else { None };
     
-    // Map dithered brightness to ASCII character
-    // We use a smooth mapping instead of hard thresholds
-    if dithered_brightness > 0.85 {
-        match wall_type {
-            0 => '█', // North
-            1 => '█', // South
-            2 => '█', // West
-            3 => '█', // East
-            _ => '█',
+    match hit.material {
+        WallMaterial::Solid => Some(solid()),
+        WallMaterial::Grate => {
+            let on_bar = (u * 4.0).rem_euclid(1.0) < 0.2;
+            let on_rail = !(0.06..=0.94).contains(&v);
+            match (on_bar, on_rail) {
+                (true, true) => Some(WallPixel::Glyph('┼')),
+                (true, false) => Some(WallPixel::Glyph('│')),
+                (false, true) => Some(WallPixel::Glyph('─')),
+                (false, false) => None,
+            }
         }
-    } else if dithered_brightness > 0.65 {
-        match wall_type {
-            0 => '▓',
-            1 => '▓',
-            2 => '▓',
-            3 => '▓',
-            _ => '▓',
+        WallMaterial::Glass => glint(),
+        WallMaterial::Window => {
+            if !(0.2..0.8).contains(&u) || !(0.25..0.75).contains(&v) {
+                Some(solid())
+            } else {
+                glint()
+            }
+        }
+        WallMaterial::Door { open } => {
+            if u < 1.0 - open {
+                Some(solid())
+            } else {
+                None
+            }
         }
-    } else if dithered_brightness > 0.45 {
-        '▒'
-    } else if dithered_brightness > 0.25 {
-        '░'
-    } else {
-        '·'
     }
 }
 
@@ -343,3 +674,121 @@ pub fn get_color(distance: f64, max_distance: f64) -> u8 {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{HALF_WALL_HEIGHT, PILLAR_HEIGHT};
+    use crate::test_mazes;
+    
+    // A 7x3 corridor running east, closed at both ends
+    fn corridor() -> Maze {
+        test_mazes::corridor(7, 6)
+    }
+    
+    #[test]
+    fn test_ray_stops_at_full_wall() {
+        let maze = corridor();
+        let result = cast_ray(1.5, 1.5, 0.0, &maze, 20.0, None, None);
+        assert!((result.distance - 4.5).abs() < 1e-9);
+        assert!(result.hits.is_empty());
+    }
+    
+    #[test]
+    fn test_ray_passes_over_half_wall() {
+        let mut maze = corridor();
+        maze.cells[1][3] = true;
+        maze.heights[1][3] = HALF_WALL_HEIGHT;
+        
+        let result = cast_ray(1.5, 1.5, 0.0, &maze, 20.0, None, None);
+        assert_eq!(result.hits.len(), 1);
+        assert!((result.hits[0].distance - 1.5).abs() < 1e-9);
+        assert_eq!(result.hits[0].wall_height, HALF_WALL_HEIGHT);
+        assert!((result.distance - 4.5).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_full_walls_block_rays_in_a_maze_with_pillars() {
+        // A pillar anywhere in the maze doesn't let rays run on past full walls
+        let mut heights = vec![vec![FULL_WALL_HEIGHT; 7]; 3];
+        heights[0][0] = PILLAR_HEIGHT;
+        let mut cells = vec![vec![true; 7]; 3];
+        cells[1][1..6].fill(false);
+        let maze = Maze::from_layout(cells.clone(), heights.clone(), vec![vec![WallMaterial::Solid; 7]; 3], (1, 1), (6, 1));
+        let hits = cast_ray_all(1.5, 1.5, 0.0, &maze, 20.0);
+        assert_eq!(hits.iter().map(|hit| hit.cell).collect::<Vec<_>>(), vec![(6, 1)]);
+        
+        // A pillar behind a full wall shows above it, so the ray goes on to it
+        cells[1][3] = true;
+        cells[1][5] = true;
+        heights[1][5] = PILLAR_HEIGHT;
+        let maze = Maze::from_layout(cells, heights, vec![vec![WallMaterial::Solid; 7]; 3], (1, 1), (6, 1));
+        let result = cast_ray(1.5, 1.5, 0.0, &maze, 20.0, None, None);
+        assert_eq!(result.hits.iter().map(|hit| hit.cell).collect::<Vec<_>>(), vec![(3, 1)]);
+        assert!((result.distance - 3.5).abs() < 1e-9);
+        assert_eq!(result.wall_height, PILLAR_HEIGHT);
+        // Only what's in front of the full wall is in view for the map
+        assert_eq!(result.cells.last(), Some(&(3, 1)));
+    }
+    
+    #[test]
+    fn test_cast_ray_all_sees_through_glass() {
+        let mut maze = corridor();
+        maze.cells[1][2] = true;
+        maze.materials[1][2] = WallMaterial::Glass;
+        maze.cells[1][4] = true;
+        maze.materials[1][4] = WallMaterial::Door { open: 0.5 };
+        
+        let hits = cast_ray_all(1.5, 1.5, 0.0, &maze, 20.0);
+        let cells: Vec<(i32, i32)> = hits.iter().map(|hit| hit.cell).collect();
+        assert_eq!(cells, vec![(2, 1), (4, 1), (6, 1)]);
+        assert!(hits.windows(2).all(|pair| pair[0].distance < pair[1].distance));
+        
+        // Glass still blocks movement
+        assert!(maze.get_cell(2.5, 1.5));
+    }
+    
+    #[test]
+    fn test_ray_lists_every_cell_it_crosses() {
+        let maze = test_mazes::room((6, 4), (9, 9));
+        let result = cast_ray(2.5, 5.5, -0.4, &maze, 20.0, None, None);
+        assert_eq!(result.cells.first(), Some(&(2, 5)));
+        // Each cell is a step sideways from the last, never a diagonal jump
+        for pair in result.cells.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert_eq!((a.0 - b.0).abs() + (a.1 - b.1).abs(), 1, "{:?}", pair);
+        }
+        let last = *result.cells.last().unwrap();
+        assert!(maze.is_wall(last.0 as usize, last.1 as usize));
+    }
+    
+    #[test]
+    fn test_cast_ray_all_respects_max_distance() {
+        let maze = corridor();
+        let hits = cast_ray_all(1.5, 1.5, 0.0, &maze, 3.0);
+        assert!(hits.is_empty());
+        
+        let result = cast_ray(1.5, 1.5, 0.0, &maze, 3.0, None, None);
+        assert_eq!(result.distance, 3.0);
+    }
+    
+    #[test]
+    fn test_wall_span_shares_bottom_edge() {
+        let (full_start, full_end) = wall_span(2.0, FULL_WALL_HEIGHT, 40);
+        assert_eq!((full_start, full_end), (10, 30));
+        
+        let (half_start, half_end) = wall_span(2.0, HALF_WALL_HEIGHT, 40);
+        assert!(half_start > full_start);
+        assert_eq!(half_end, full_end);
+    }
+    
+    #[test]
+    fn test_height_above_floor_ignores_clipping() {
+        // Fully on screen: the span runs from one wall high down to the floor
+        assert!((height_above_floor(2.0, 10, 40) - 0.975).abs() < 1e-9);
+        assert!((height_above_floor(2.0, 29, 40) - 0.025).abs() < 1e-9);
+        
+        // Close enough to be cut off, the middle row still sits halfway up
+        assert!((height_above_floor(0.6, 20, 40) - 0.4925).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,411 @@
+use crate::braille;
+use crate::dither::{DitherConfig, DitherMethod, Sample};
+use crate::glyphs::GlyphRamp;
+use crate::maze::Maze;
+use crate::overlay::WIDE_TAIL;
+use crate::parallel::{for_each_indexed, map_range, Threading};
+use crate::raycast::{along_wall, cast_ray, cast_ray_thin, get_wall_pixel, height_above_floor, wall_sample, wall_span, RaycastResult, WallPixel};
+use crate::thin_maze::ThinMaze;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Sprites any closer than this are inside the camera and not drawn
+const MIN_SPRITE_DISTANCE: f64 = 0.2;
+// Fractions of a sprite's height taken by its head, and where its hem starts
+const HEAD_HEIGHT: f64 = 0.35;
+const HEM_HEIGHT: f64 = 0.85;
+
+/// How the 3D view is turned into characters.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum RenderMode {
+    #[default]
+    Glyphs, // One sample per character cell, shaded with the glyph ramp
+    Braille, // Eight samples per cell, drawn as a 2x4 braille dot matrix
+}
+
+/// Where the view is rendered from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64, // Radians, 0.0 facing east
+    pub fov: f64, // Horizontal field of view in radians
+    pub max_distance: f64, // Nothing further away is drawn
+}
+
+impl Camera {
+    /// A camera with the game's 90 degree field of view and 20 unit draw distance.
+    pub fn new(x: f64, y: f64, angle: f64) -> Self {
+        Camera {
+            x,
+            y,
+            angle,
+            fov: std::f64::consts::PI / 2.0, // 90 degrees (zoomed out)
+            max_distance: 20.0,
+        }
+    }
+}
+
+/// Everything about a frame besides the camera and the maze.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub width: usize, // In character cells
+    pub height: usize,
+    pub mode: RenderMode,
+    pub glyph_ramp: GlyphRamp, // Used in Glyphs mode; braille has its own dot ramp
+    pub dither_method: DitherMethod,
+    pub dither_config: DitherConfig,
+    pub threading: Threading,
+}
+
+impl RenderOptions {
+    /// Default display settings at the given size.
+    pub fn new(width: usize, height: usize) -> Self {
+        RenderOptions {
+            width,
+            height,
+            mode: RenderMode::default(),
+            glyph_ramp: GlyphRamp::default(),
+            dither_method: DitherMethod::default(),
+            dither_config: DitherConfig::default(),
+            threading: Threading::default(),
+        }
+    }
+}
+
+/// A rendered view: rows of character cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Vec<char>>, // height rows of width cells
+    pub surfaces: Vec<Vec<bool>>, // True where a cell shows a dithered wall
+    pub depths: Vec<f64>, // Distance to the nearest wall in each column, for drawing sprites behind walls
+}
+
+impl fmt::Display for Frame {
+    // One line per row, joined with newlines and no trailing newline
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (row, line) in self.cells.iter().enumerate() {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            for &ch in line.iter().filter(|&&ch| ch != WIDE_TAIL) {
+                write!(f, "{}", ch)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A figure standing on the floor, drawn over the view as a see-through
+/// silhouette: a rounded head, a body and a ragged hem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64, // In world units
+    pub height: f64, // In multiples of a standard wall
+    pub glyph: char, // Drawn on every other cell of the silhouette, so the view shows through
+}
+
+// What the rays are cast against
+#[derive(Clone, Copy)]
+enum World<'a> {
+    Blocks(&'a Maze),
+    Thin(&'a ThinMaze),
+}
+
+/// Draws the first-person view of a maze.
+/// 
+/// Rendering is a pure function of its inputs: no clock, no files, no game
+/// state. The same camera, maze and options always give the same frame, on
+/// any number of threads. Overlays such as the win screen are the caller's
+/// business.
+/// 
+/// The renderer holds no state of its own; the dither tables it samples are
+/// shared and built on first use.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Renderer;
+
+impl Renderer {
+    pub fn new() -> Self {
+        Renderer
+    }
+    
+    /// Renders a maze with walls filling whole grid cells. The exit cell's
+    /// threshold is left open.
+    pub fn render(&self, camera: &Camera, maze: &Maze, options: &RenderOptions) -> Frame {
+        self.render_world(camera, World::Blocks(maze), options)
+    }
+    
+    /// Renders a maze with thin walls between cells.
+    pub fn render_thin(&self, camera: &Camera, maze: &ThinMaze, options: &RenderOptions) -> Frame {
+        self.render_world(camera, World::Thin(maze), options)
+    }
+    
+    fn render_world(&self, camera: &Camera, world: World, options: &RenderOptions) -> Frame {
+        let (width, height) = (options.width, options.height);
+        let (cells, surfaces, depths) = match options.mode {
+            RenderMode::Glyphs => shade_pixels(camera, world, width, height, &options.glyph_ramp, options),
+            RenderMode::Braille => {
+                // Every character cell holds a 2x4 block of dots, each shaded on its own
+                let (dots, dot_surfaces, dot_depths) =
+                    shade_pixels(camera, world, width * 2, height * 4, &braille::dot_ramp(), options);
+                let surfaces = (0..height)
+                    .map(|row| {
+                        (0..width)
+                            .map(|col| (0..4).any(|dy| dot_surfaces[row * 4 + dy][col * 2..col * 2 + 2].contains(&true)))
+                            .collect()
+                    })
+                    .collect();
+                let depths = dot_depths.chunks(2).map(|pair| pair.iter().copied().fold(f64::INFINITY, f64::min)).collect();
+                (braille::pack(&dots, width, height), surfaces, depths)
+            }
+        };
+        
+        Frame { width, height, cells, surfaces, depths }
+    }
+    
+    /// Draws a sprite into a frame rendered from `camera`, hidden by any
+    /// wall in front of it.
+    pub fn draw_sprite(&self, frame: &mut Frame, camera: &Camera, sprite: &Sprite) {
+        let (dx, dy) = (sprite.x - camera.x, sprite.y - camera.y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance < MIN_SPRITE_DISTANCE || distance >= camera.max_distance {
+            return;
+        }
+        
+        // Columns are spread evenly by angle, so place the sprite by its bearing
+        let pi = std::f64::consts::PI;
+        let bearing = (dy.atan2(dx) - camera.angle + pi).rem_euclid(2.0 * pi) - pi;
+        let columns_per_radian = frame.width as f64 / camera.fov;
+        let center = (bearing + camera.fov / 2.0) * columns_per_radian;
+        let half_width = (sprite.width / 2.0).atan2(distance) * columns_per_radian;
+        let (top, bottom) = wall_span(distance, sprite.height, frame.height);
+        if half_width <= 0.0 || bottom <= top {
+            return;
+        }
+        
+        let first = (center - half_width).max(0.0) as usize;
+        let last = ((center + half_width).ceil().max(0.0) as usize).min(frame.width);
+        for col in first..last {
+            if frame.depths[col] <= distance {
+                continue;
+            }
+            let u = (col as f64 + 0.5 - center) / half_width;
+            for row in top..bottom {
+                let v = (row - top) as f64 / (bottom - top) as f64;
+                if (row + col) % 2 == 0 && in_silhouette(u, v) {
+                    frame.cells[row][col] = sprite.glyph;
+                }
+            }
+        }
+    }
+}
+
+// Whether a point of a sprite's box is inside its silhouette; `u` runs -1.0
+// to 1.0 across it and `v` 0.0 to 1.0 down it
+fn in_silhouette(u: f64, v: f64) -> bool {
+    if u.abs() > 1.0 {
+        false
+    } else if v < HEAD_HEIGHT {
+        // Round head
+        let above = (HEAD_HEIGHT - v) / HEAD_HEIGHT;
+        u * u + above * above <= 1.0
+    } else if v > HEM_HEIGHT {
+        // Ragged hem: three points reaching down to the floor
+        ((u + 1.0) * 3.0) % 2.0 < 1.0
+    } else {
+        true
+    }
+}
+
+/// Casts one ray per column and shades every pixel of the view,
+/// drawing walls and floor with glyphs from `ramp`.
+// Returns the shaded pixels, which of them show a dithered surface, and each
+// column's distance to its nearest wall
+fn shade_pixels(
+    camera: &Camera,
+    world: World,
+    width: usize,
+    height: usize,
+    ramp: &GlyphRamp,
+    options: &RenderOptions,
+) -> (Vec<Vec<char>>, Vec<Vec<bool>>, Vec<f64>) {
+    let (fov, max_distance, threading) = (camera.fov, camera.max_distance, options.threading);
+    
+    // Pre-calculate raycast results for each column
+    let column_data: Vec<RaycastResult> = map_range(width, threading, |col| {
+        let ray_angle = camera.angle - fov / 2.0 + (col as f64 / width as f64) * fov;
+        match world {
+            World::Thin(thin) => cast_ray_thin(camera.x, camera.y, ray_angle, thin, max_distance),
+            World::Blocks(maze) => cast_ray(
+                camera.x,
+                camera.y,
+                ray_angle,
+                maze,
+                max_distance,
+                Some(maze.exit.0 as f64 + 0.5),
+                Some(maze.exit.1 as f64 + 0.5),
+            ),
+        }
+    });
+    
+    let dither_config = &options.dither_config;
+    
+    // Render row by row: fixed glyphs go straight into the row, shaded
+    // surfaces are collected as samples for the ditherer
+    let (mut pixels, mut samples): (Vec<Vec<char>>, Vec<Vec<Option<Sample>>>) = map_range(height, threading, |row| {
+        let mut line = vec![' '; width];
+        let mut shaded = vec![None; width];
+        
+        for (col, result) in column_data.iter().enumerate() {
+            let (distance, passed_exit, exit_threshold_dist, hit_x, hit_y) = (
+                result.distance,
+                result.passed_exit,
+                result.exit_threshold_dist,
+                result.hit_x,
+                result.hit_y,
+            );
+            
+            // Calculate wall extent based on distance (perspective projection)
+            let wall_render_dist = distance;
+            let (wall_start, wall_end) = wall_span(wall_render_dist, result.wall_height, height);
+            
+            if row < wall_start {
+                // Ceiling
+                line[col] = ' ';
+            } else if row < wall_end {
+                // Wall - if ray passed through exit threshold, make threshold line invisible
+                let on_threshold = passed_exit
+                    && exit_threshold_dist.is_some_and(|threshold_dist| (wall_render_dist - threshold_dist).abs() < 0.2);
+                if on_threshold {
+                    line[col] = ' '; // Invisible exit threshold line
+                } else {
+                    // Per-pixel shading, at the point on the wall face this pixel shows
+                    let surface = (
+                        along_wall(result.wall_type, hit_x, hit_y),
+                        height_above_floor(distance, row, height),
+                    );
+                    shaded[col] = Some(wall_sample(distance, max_distance, hit_x, hit_y, surface, (col as f64, row as f64), dither_config));
+                }
+            } else {
+                // Floor - stop at exit threshold if ray passed through exit
+                let floor_dist = calculate_floor_distance(row, height);
+                let floor_limit = if passed_exit {
+                    exit_threshold_dist.unwrap_or(0.0)
+                } else {
+                    max_distance
+                };
+                line[col] = if floor_dist < floor_limit {
+                    get_floor_char(floor_dist, max_distance, ramp)
+                } else {
+                    ' '
+                };
+            }
+            
+            // Paint shorter and see-through walls over the background, back to front,
+            // so nearer ones win and gaps in them show what lies behind
+            for wall in result.hits.iter().rev() {
+                let (wall_start, wall_end) = wall_span(wall.distance, wall.wall_height, height);
+                if row < wall_start || row >= wall_end {
+                    continue;
+                }
+                let v = (row - wall_start) as f64 / (wall_end - wall_start) as f64;
+                let up = height_above_floor(wall.distance, row, height);
+                match get_wall_pixel(wall, v, up, (col as f64, row as f64), max_distance, dither_config) {
+                    Some(WallPixel::Shaded(sample)) => shaded[col] = Some(sample),
+                    Some(WallPixel::Glyph(ch)) => {
+                        line[col] = ch;
+                        shaded[col] = None;
+                    }
+                    None => {}
+                }
+            }
+        }
+        
+        (line, shaded)
+    })
+    .into_iter()
+    .unzip();
+    
+    // Dither the whole frame at once, then turn each shade into a glyph
+    options.dither_method.ditherer().dither_frame(&mut samples, &ramp.levels(), dither_config, threading);
+    for_each_indexed(&mut pixels, threading, |row, line| {
+        for (col, sample) in samples[row].iter().enumerate() {
+            if let Some(sample) = sample {
+                line[col] = ramp.glyph(sample.brightness);
+            }
+        }
+    });
+    
+    let surfaces = samples.iter().map(|row| row.iter().map(Option::is_some).collect()).collect();
+    let depths = column_data
+        .iter()
+        .map(|result| result.hits.first().map_or(result.distance, |hit| hit.distance.min(result.distance)))
+        .collect();
+    (pixels, surfaces, depths)
+}
+
+fn calculate_floor_distance(row: usize, height: usize) -> f64 {
+    let p = (row as f64 - height as f64 / 2.0) / (height as f64 / 2.0);
+    1.0 / p.max(0.1)
+}
+
+fn get_floor_char(distance: f64, max_distance: f64, ramp: &GlyphRamp) -> char {
+    let normalized_dist = (distance / max_distance).min(1.0);
+    ramp.floor_glyph(normalized_dist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_mazes;
+    
+    // A 7x3 corridor running east, with the exit behind the camera
+    fn corridor() -> Maze {
+        test_mazes::corridor(7, 0)
+    }
+    
+    #[test]
+    fn test_render_is_repeatable_and_sized() {
+        let camera = Camera::new(1.5, 1.5, 0.0);
+        let maze = corridor();
+        let mut options = RenderOptions::new(40, 12);
+        options.threading = Threading::Single;
+        
+        let frame = Renderer::new().render(&camera, &maze, &options);
+        assert_eq!(frame.cells.len(), 12);
+        assert!(frame.cells.iter().all(|line| line.len() == 40));
+        assert_eq!(frame.to_string().lines().count(), 12);
+        // The end wall straight ahead fills the middle of the view
+        assert!(frame.surfaces[5][20]);
+        
+        options.threading = Threading::Parallel;
+        assert_eq!(Renderer::new().render(&camera, &maze, &options), frame);
+        
+        options.mode = RenderMode::Braille;
+        let braille = Renderer::new().render(&camera, &maze, &options);
+        assert_eq!((braille.width, braille.height), (40, 12));
+        assert!(braille.surfaces[5][20]);
+    }
+    
+    #[test]
+    fn test_sprite_is_hidden_behind_walls() {
+        let camera = Camera::new(1.5, 1.5, 0.0);
+        let maze = corridor();
+        let options = RenderOptions::new(40, 12);
+        let renderer = Renderer::new();
+        let count = |x: f64| {
+            let mut frame = renderer.render(&camera, &maze, &options);
+            renderer.draw_sprite(&mut frame, &camera, &Sprite { x, y: 1.5, width: 0.5, height: 0.8, glyph: '@' });
+            frame.cells.iter().flatten().filter(|&&ch| ch == '@').count()
+        };
+        
+        // In the corridor ahead, beyond its end wall, and behind the camera
+        assert!(count(3.5) > 0);
+        assert_eq!(count(8.5), 0);
+        assert_eq!(count(0.5), 0);
+    }
+}
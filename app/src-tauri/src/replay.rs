@@ -0,0 +1,493 @@
+use crate::clock::Clock;
+use crate::error::GameError;
+use crate::game::{GameState, MazeStyle, PlayerInput, MAX_FRAME_TIME, TICK, TICK_RATE};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// Replay file format version, bumped whenever old replays would play back differently
+const VERSION: u32 = 1;
+// Keys for the movement buttons, in bit order
+const BUTTON_KEYS: [char; 6] = ['w', 's', 'a', 'd', 'q', 'e'];
+// Playback speed limits, in replay seconds per real second
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 4.0;
+
+/// What the player did during one simulation tick.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct TickInput {
+    pub buttons: u8, // One bit per movement key, in `BUTTON_KEYS` order
+    pub mouse: f64, // Mouse movement turned by at the start of the tick, in `mouse_delta_x` units
+}
+
+impl TickInput {
+    pub fn new(input: &PlayerInput, mouse: f64) -> Self {
+        let held = [input.forward, input.backward, input.left, input.right, input.turn_left, input.turn_right];
+        let buttons = held
+            .iter()
+            .enumerate()
+            .filter(|(_, &down)| down)
+            .fold(0, |bits, (bit, _)| bits | 1 << bit);
+        TickInput { buttons, mouse }
+    }
+    
+    pub fn forward(&self) -> bool {
+        self.held(0)
+    }
+    
+    pub fn backward(&self) -> bool {
+        self.held(1)
+    }
+    
+    pub fn left(&self) -> bool {
+        self.held(2)
+    }
+    
+    pub fn right(&self) -> bool {
+        self.held(3)
+    }
+    
+    pub fn turn_left(&self) -> bool {
+        self.held(4)
+    }
+    
+    pub fn turn_right(&self) -> bool {
+        self.held(5)
+    }
+    
+    fn held(&self, bit: usize) -> bool {
+        self.buttons & (1 << bit) != 0
+    }
+    
+    /// A frame of exactly one tick that plays this input back through
+    /// `GameState::update`.
+    pub fn frame(&self) -> PlayerInput {
+        PlayerInput {
+            forward: self.forward(),
+            backward: self.backward(),
+            left: self.left(),
+            right: self.right(),
+            turn_left: self.turn_left(),
+            turn_right: self.turn_right(),
+            mouse_delta_x: self.mouse,
+            delta_time: TICK,
+        }
+    }
+}
+
+/// Everything needed to play a level again: which maze it was, and the
+/// input of every tick.
+/// 
+/// Inputs are stored run-length encoded, since keys are usually held for
+/// many ticks in a row. As text a replay looks like
+/// 
+/// ```text
+/// # Matrix Maze replay
+/// version = 1
+/// level = 2
+/// style = Blocks
+/// seed = 5f0c3a91d2e7b804
+/// 84 w
+/// 12 wq
+/// 1 w 0.37
+/// 30 -
+/// ```
+/// 
+/// where each line after the header is a number of ticks, the keys held
+/// (`-` for none) and, if the mouse moved, how far.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Replay {
+    pub level: u8,
+    pub maze_style: MazeStyle,
+    pub seed: u64,
+    pub runs: Vec<(TickInput, u32)>, // Each input, and how many ticks in a row it was played
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay::new(1, MazeStyle::default(), 0)
+    }
+}
+
+impl Replay {
+    pub fn new(level: u8, maze_style: MazeStyle, seed: u64) -> Self {
+        Replay { level, maze_style, seed, runs: Vec::new() }
+    }
+    
+    /// Adds the next tick's input.
+    pub fn push(&mut self, input: TickInput) {
+        match self.runs.last_mut() {
+            Some((last, ticks)) if *last == input => *ticks += 1,
+            _ => self.runs.push((input, 1)),
+        }
+    }
+    
+    /// Number of ticks recorded.
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|&(_, ticks)| ticks as usize).sum()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+    
+    /// Length of the recording in seconds of game time.
+    pub fn duration(&self) -> f64 {
+        self.len() as f64 * TICK
+    }
+    
+    /// The input of every tick, in order.
+    pub fn inputs(&self) -> impl Iterator<Item = TickInput> + '_ {
+        self.runs.iter().flat_map(|&(input, ticks)| std::iter::repeat_n(input, ticks as usize))
+    }
+    
+    /// Where replays are saved by default: `replays/` next to the best times,
+    /// named after the level and seed.
+    pub fn default_path(&self) -> PathBuf {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.pop(); // Go from src-tauri to app/
+        path.push("replays");
+        path.push(format!("level-{}-{:016x}.txt", self.level, self.seed));
+        path
+    }
+    
+    pub fn save(&self, path: &Path) -> Result<(), GameError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| GameError::io(dir, e))?;
+        }
+        fs::write(path, self.to_string()).map_err(|e| GameError::io(path, e))
+    }
+    
+    pub fn load(path: &Path) -> Result<Self, GameError> {
+        fs::read_to_string(path).map_err(|e| GameError::io(path, e))?.parse()
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Matrix Maze replay")?;
+        writeln!(f, "version = {}", VERSION)?;
+        writeln!(f, "level = {}", self.level)?;
+        writeln!(f, "style = {:?}", self.maze_style)?;
+        writeln!(f, "seed = {:016x}", self.seed)?;
+        for &(input, ticks) in &self.runs {
+            let keys: String = BUTTON_KEYS
+                .iter()
+                .enumerate()
+                .filter(|&(bit, _)| input.held(bit))
+                .map(|(_, &key)| key)
+                .collect();
+            write!(f, "{} {}", ticks, if keys.is_empty() { "-" } else { &keys })?;
+            // Shortest text that parses back to exactly the same number
+            if input.mouse != 0.0 {
+                write!(f, " {}", input.mouse)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = GameError;
+    
+    fn from_str(text: &str) -> Result<Self, GameError> {
+        let bad = |message: String| GameError::BadState { message };
+        let mut version = None;
+        let mut level = None;
+        let mut maze_style = None;
+        let mut seed = None;
+        let mut runs = Vec::new();
+        
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            
+            if let Some((key, value)) = line.split_once('=') {
+                let (key, value) = (key.trim(), value.trim());
+                let invalid = || bad(format!("Line {}: invalid {} `{}`", number + 1, key, value));
+                match key {
+                    "version" => version = Some(value.parse::<u32>().map_err(|_| invalid())?),
+                    "level" => {
+                        let parsed = value.parse::<u8>().map_err(|_| invalid())?;
+                        if !(1..=5).contains(&parsed) {
+                            return Err(GameError::UnknownLevel { level: parsed });
+                        }
+                        level = Some(parsed);
+                    }
+                    "style" => {
+                        maze_style = Some(match value {
+                            "Blocks" => MazeStyle::Blocks,
+                            "ThinWalls" => MazeStyle::ThinWalls,
+                            _ => return Err(invalid()),
+                        })
+                    }
+                    "seed" => seed = Some(u64::from_str_radix(value, 16).map_err(|_| invalid())?),
+                    _ => return Err(bad(format!("Line {}: unknown key `{}`", number + 1, key))),
+                }
+                continue;
+            }
+            
+            let mut fields = line.split_whitespace();
+            let ticks = fields
+                .next()
+                .and_then(|ticks| ticks.parse::<u32>().ok())
+                .filter(|&ticks| ticks > 0)
+                .ok_or_else(|| bad(format!("Line {}: expected a tick count", number + 1)))?;
+            let keys = fields.next().unwrap_or("-");
+            let mut buttons = 0;
+            for key in keys.chars().filter(|&key| key != '-') {
+                let bit = BUTTON_KEYS
+                    .iter()
+                    .position(|&k| k == key)
+                    .ok_or_else(|| bad(format!("Line {}: unknown button `{}`", number + 1, key)))?;
+                buttons |= 1 << bit;
+            }
+            let mouse = match fields.next() {
+                Some(mouse) => mouse
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|mouse| mouse.is_finite())
+                    .ok_or_else(|| bad(format!("Line {}: `{}` is not a number", number + 1, mouse)))?,
+                None => 0.0,
+            };
+            if fields.next().is_some() {
+                return Err(bad(format!("Line {}: expected `<ticks> <keys> [mouse]`", number + 1)));
+            }
+            runs.push((TickInput { buttons, mouse }, ticks));
+        }
+        
+        match version {
+            Some(VERSION) => {}
+            Some(other) => return Err(bad(format!("Replay version {} can't be played by this version of the game", other))),
+            None => return Err(bad("Not a replay: missing `version`".to_string())),
+        }
+        let missing = |key| bad(format!("Replay is missing its `{}`", key));
+        Ok(Replay {
+            level: level.ok_or_else(|| missing("level"))?,
+            maze_style: maze_style.ok_or_else(|| missing("style"))?,
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            runs,
+        })
+    }
+}
+
+/// A change to how a replay is being played.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PlaybackControl {
+    TogglePause,
+    Speed(f64), // Replay seconds per real second, e.g. 2.0 for double speed
+    Seek(usize), // Jump to just after this many ticks
+}
+
+/// Plays a replay back through `GameState::update`, one recorded tick at a
+/// time, so the level unfolds exactly as it did when it was recorded.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub state: GameState, // The level as of `tick`
+    pub tick: usize, // Ticks played so far
+    pub run: usize, // Run in `replay.runs` the next tick's input comes from
+    pub run_tick: u32, // Ticks of that run already played
+    pub paused: bool,
+    pub speed: f64, // Replay seconds per real second
+    pub accumulator: f64, // Scaled frame time not yet played, always less than one tick
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        let state = Self::start(&replay);
+        ReplayPlayer {
+            replay,
+            state,
+            tick: 0,
+            run: 0,
+            run_tick: 0,
+            paused: false,
+            speed: 1.0,
+            accumulator: 0.0,
+        }
+    }
+    
+    /// The level as it was before the first tick.
+    pub(crate) fn start(replay: &Replay) -> GameState {
+        let mut state = GameState::from_seed(replay.level, replay.maze_style, replay.seed);
+        state.is_replay = true;
+        state.events.clear();
+        state
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.replay.len()
+    }
+    
+    /// Seconds of game time played so far.
+    pub fn time(&self) -> f64 {
+        self.tick as f64 * TICK
+    }
+    
+    pub fn control(&mut self, control: PlaybackControl) {
+        match control {
+            PlaybackControl::TogglePause => self.paused = !self.paused,
+            PlaybackControl::Speed(speed) => self.speed = speed.clamp(MIN_SPEED, MAX_SPEED),
+            PlaybackControl::Seek(tick) => self.seek(tick),
+        }
+    }
+    
+    /// Advances playback by one frame of real time, scaled by the speed.
+    pub fn update(&mut self, delta_time: f64) {
+        if !self.paused {
+            self.accumulator += delta_time.clamp(0.0, MAX_FRAME_TIME) * self.speed;
+        }
+        while self.accumulator >= TICK {
+            let Some(input) = self.next_input() else {
+                self.accumulator = 0.0;
+                break;
+            };
+            Self::play(&mut self.state, &input);
+            self.accumulator -= TICK;
+        }
+        // Let the camera blend towards the next tick like it does in the game
+        self.state.tick_accumulator = self.accumulator;
+    }
+    
+    /// Jumps to just after `tick` ticks. The level is rebuilt from its seed
+    /// and replayed from the start when seeking backwards.
+    pub fn seek(&mut self, tick: usize) {
+        let tick = tick.min(self.replay.len());
+        if tick < self.tick {
+            self.state = Self::start(&self.replay);
+            self.tick = 0;
+            self.run = 0;
+            self.run_tick = 0;
+        }
+        while self.tick < tick {
+            let Some(input) = self.next_input() else { break };
+            Self::play(&mut self.state, &input);
+        }
+        self.accumulator = 0.0;
+        self.state.tick_accumulator = 0.0;
+    }
+    
+    /// The input of the next tick, moving past it.
+    fn next_input(&mut self) -> Option<TickInput> {
+        let &(input, ticks) = self.replay.runs.get(self.run)?;
+        self.run_tick += 1;
+        if self.run_tick >= ticks {
+            self.run += 1;
+            self.run_tick = 0;
+        }
+        self.tick += 1;
+        Some(input)
+    }
+    
+    /// Runs exactly one tick. The state's accumulator is emptied first, so a
+    /// one-tick frame can't run two. Events are dropped as they happen,
+    /// since nothing listens to a level that's only being replayed.
+    pub(crate) fn play(state: &mut GameState, input: &TickInput) {
+        state.tick_accumulator = 0.0;
+        state.update(&input.frame());
+        state.events.clear();
+    }
+    
+    /// Renders the replayed level. The start message is timed by the
+    /// replay, not by the wall clock.
+    pub fn render_frame(&mut self, width: usize, height: usize) -> String {
+        let clock = ReplayClock { ticks: self.tick };
+        self.state.render_frame(width, height, &clock)
+    }
+}
+
+/// Time as the replay sees it: the ticks played since the level started.
+struct ReplayClock {
+    ticks: usize,
+}
+
+impl Clock for ReplayClock {
+    fn now(&self) -> f64 {
+        self.ticks as f64 / TICK_RATE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Plays a level live with uneven frame times, weaving, strafing and
+    // nudging the mouse, and returns the final state
+    fn play_live(frames: usize) -> GameState {
+        let mut state = GameState::from_seed(2, MazeStyle::ThinWalls, 0x5eed);
+        for frame in 0..frames {
+            let weave = (frame / 25) % 2 == 0;
+            state.update(&PlayerInput {
+                forward: frame % 90 < 70,
+                left: frame % 90 >= 80,
+                turn_left: weave,
+                turn_right: !weave && frame % 3 == 0,
+                mouse_delta_x: if frame % 17 == 0 { 0.37 } else { 0.0 },
+                delta_time: 0.009 + (frame % 5) as f64 * 0.004,
+                ..PlayerInput::default()
+            });
+        }
+        state
+    }
+    
+    fn pose(state: &GameState) -> (f64, f64, f64, f64, bool) {
+        (state.player_x, state.player_y, state.player_angle, state.level_elapsed, state.has_won)
+    }
+    
+    #[test]
+    fn test_replay_reproduces_live_run() {
+        let live = play_live(400);
+        let text = live.replay.to_string();
+        let replay: Replay = text.parse().unwrap();
+        assert_eq!(replay, live.replay);
+        // Held keys collapse into runs
+        assert!(replay.runs.len() < replay.len() / 3);
+        
+        let mut player = ReplayPlayer::new(replay);
+        while !player.is_finished() {
+            player.update(1.0 / 60.0);
+        }
+        assert_eq!(pose(&player.state), pose(&live));
+        // Events the replayed level raises aren't kept waiting for a listener
+        assert!(player.state.events.is_empty());
+    }
+    
+    #[test]
+    fn test_seek_pause_and_speed() {
+        let replay = play_live(300).replay;
+        let mut straight = ReplayPlayer::new(replay.clone());
+        straight.seek(200);
+        
+        // Seeking past the end and back lands on the same state as playing straight there
+        let mut player = ReplayPlayer::new(replay);
+        player.seek(usize::MAX);
+        assert!(player.is_finished());
+        player.seek(200);
+        assert_eq!(pose(&player.state), pose(&straight.state));
+        assert_eq!((player.run, player.run_tick), (straight.run, straight.run_tick));
+        
+        player.control(PlaybackControl::TogglePause);
+        player.update(0.05);
+        assert_eq!(player.tick, 200);
+        
+        player.control(PlaybackControl::TogglePause);
+        player.control(PlaybackControl::Speed(2.0));
+        player.update(0.05);
+        assert_eq!(player.tick, 212);
+    }
+    
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let replay = "version = 1\nlevel = 1\nstyle = Blocks\nseed = 2a\n10 wz\n";
+        assert_eq!(replay.parse::<Replay>().unwrap_err().to_string(), "Line 5: unknown button `z`");
+        assert!("version = 1\nlevel = x\n".parse::<Replay>().unwrap_err().to_string().starts_with("Line 2: invalid level"));
+        assert_eq!("version = 1\nlevel = 9\n".parse::<Replay>().unwrap_err(), GameError::UnknownLevel { level: 9 });
+        assert!("10 w\n".parse::<Replay>().unwrap_err().to_string().contains("missing `version`"));
+    }
+}
@@ -0,0 +1,544 @@
+use crate::automap;
+use crate::clock::Clock;
+use crate::dither::{DitherConfig, DitherMethod};
+use crate::error::GameError;
+use crate::events::{GameEvent, SessionEvent};
+use crate::flow::{FlowAction, FlowKey, GameFlow, MenuItem, MenuPage, PauseMenu, Setting};
+use crate::font::{banner, Font};
+use crate::game::{GameState, MazeStyle, PlayerInput};
+use crate::ghost::{self, Ghost};
+use crate::glyphs::GlyphRamp;
+use crate::overlay::{Border, Layer, Overflow, Overlay};
+use crate::renderer::RenderMode;
+use crate::replay::{PlaybackControl, Replay, ReplayPlayer};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+// Widest or tallest frame that will be rendered, in characters
+pub const MAX_FRAME_SIZE: usize = 1000;
+// Columns taken up by a panel's border and padding
+const PANEL_CHROME: usize = 4;
+
+/// Identifies one game among those the backend is running.
+pub type SessionId = u32;
+
+/// One player's game, held by the backend between commands.
+/// 
+/// The frontend only ever sends inputs and settings, and gets back frames
+/// and a `GameView`; the game state itself never leaves the backend.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub game: GameState,
+    pub flow: GameFlow, // The game only runs while this is `Playing`
+    pub watching: Option<ReplayPlayer>, // Replay shown instead of the game while set
+    pub automap: bool, // The map of the level is shown instead of the view
+    pub save_dir: Option<PathBuf>, // Where the best times and runs are kept; nothing is kept without one
+}
+
+/// What the frontend needs to know about a session to draw its controls.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GameView {
+    pub session: SessionId,
+    pub flow: GameFlow,
+    pub current_level: u8,
+    pub maze_style: MazeStyle,
+    pub glyph_ramp: String, // Name of the ramp
+    pub render_mode: RenderMode,
+    pub dither_method: DitherMethod,
+    pub dither_config: DitherConfig,
+    pub playback: Option<PlaybackView>, // Set while a replay is being watched
+}
+
+/// Where playback of a replay being watched is up to.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct PlaybackView {
+    pub time: f64, // Seconds into the replay
+    pub duration: f64,
+    pub paused: bool,
+    pub speed: f64,
+}
+
+impl Session {
+    /// A new run, waiting on the title screen, keeping its best times and
+    /// runs in `save_dir`.
+    pub fn new(clock: &dyn Clock, save_dir: Option<PathBuf>) -> Self {
+        let game = GameState::new(save_dir.as_deref(), clock);
+        Session { flow: GameFlow::Title, save_dir, ..Self::with_game(game) }
+    }
+    
+    /// A session already playing `game`, which saves nothing.
+    pub fn with_game(game: GameState) -> Self {
+        Session { game, flow: GameFlow::Playing, watching: None, automap: false, save_dir: None }
+    }
+    
+    /// Advances by one frame: the replay being watched if there is one,
+    /// otherwise the game. Input with times or mouse movement that aren't
+    /// numbers is refused, since it would leave the game stuck.
+    pub fn update(&mut self, input: &PlayerInput) -> Result<(), GameError> {
+        if !input.delta_time.is_finite() || !input.mouse_delta_x.is_finite() {
+            return Err(GameError::bad_state(format!(
+                "Input has delta_time {} and mouse_delta_x {}; both must be numbers",
+                input.delta_time, input.mouse_delta_x
+            )));
+        }
+        match self.watching.as_mut() {
+            Some(player) => player.update(input.delta_time),
+            None if self.flow.is_running() => {
+                let seen = self.game.events.len();
+                self.game.update(input);
+                for event in &self.game.events[seen..] {
+                    self.flow = self.flow.on_event(event);
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+    
+    /// Handles a key that moves the game along: starting, pausing, going on
+    /// to the next level or retrying this one.
+    pub fn press(&mut self, key: FlowKey, clock: &dyn Clock) {
+        let (flow, action) = self.flow.press(key);
+        self.flow = flow;
+        match action {
+            Some(FlowAction::Begin) => self.game.level_start_time = clock.now(),
+            // After the last level, the next one is level 1 of a new run
+            Some(FlowAction::NextLevel) => self.game = self.game.next_level(clock),
+            Some(FlowAction::NewRun) => self.game = self.game.new_run(clock),
+            Some(FlowAction::RetryLevel) => self.game = self.game.retry_level(clock),
+            Some(FlowAction::ChangeSetting(setting)) => self.change_setting(setting, clock),
+            Some(FlowAction::ToggleMap) => self.automap = !self.automap && !self.game.purist,
+            None => {}
+        }
+        // A new level starts in the view, not the map of the last one
+        if matches!(action, Some(FlowAction::NextLevel | FlowAction::NewRun | FlowAction::RetryLevel)) {
+            self.automap = false;
+        }
+    }
+    
+    // Moves a setting on to its next value, as the settings page shows them
+    fn change_setting(&mut self, setting: Setting, clock: &dyn Clock) {
+        let game = &mut self.game;
+        match setting {
+            Setting::MazeStyle => {
+                let style = match game.maze_style {
+                    MazeStyle::Blocks => MazeStyle::ThinWalls,
+                    MazeStyle::ThinWalls => MazeStyle::Blocks,
+                };
+                *game = game.with_maze_style(style, clock);
+            }
+            Setting::GlyphRamp => {
+                let ramps = GlyphRamp::BUILT_IN;
+                let current = ramps.iter().position(|&name| name == game.glyph_ramp.name);
+                let next = ramps[current.map_or(0, |i| (i + 1) % ramps.len())];
+                if let Ok(ramp) = GlyphRamp::load(next) {
+                    game.glyph_ramp = ramp;
+                }
+            }
+            Setting::RenderMode => {
+                game.render_mode = match game.render_mode {
+                    RenderMode::Glyphs => RenderMode::Braille,
+                    RenderMode::Braille => RenderMode::Glyphs,
+                };
+            }
+            Setting::DitherMethod => {
+                let methods = DitherMethod::ALL;
+                let current = methods.iter().position(|&method| method == game.dither_method).unwrap_or(0);
+                game.dither_method = methods[(current + 1) % methods.len()];
+            }
+            Setting::Purist => {
+                game.purist = !game.purist;
+                self.automap = false;
+            }
+            Setting::Hud(element) => game.hud.toggle(element),
+        }
+    }
+    
+    // The pause menu as text, with the selected item marked and the page's
+    // title in block letters if they fit in `width` columns
+    fn menu_lines(&self, menu: PauseMenu, width: usize) -> Vec<String> {
+        let title = match menu.page {
+            MenuPage::Main => "PAUSED",
+            MenuPage::Settings => "SETTINGS",
+            MenuPage::Hud => "HUD",
+        };
+        let mut lines = banner(title, width, Font::Small);
+        lines.push(String::new());
+        for (i, &item) in menu.page.items().iter().enumerate() {
+            let text = match item {
+                MenuItem::Setting(setting) => format!("{}: {}", item.label(), self.setting_value(setting)),
+                _ => item.label().to_string(),
+            };
+            lines.push(if i == menu.selected { format!("> {} <", text) } else { text });
+        }
+        lines.push(String::new());
+        lines.push("ARROWS choose, SPACE selects, ESC goes back".to_string());
+        lines
+    }
+    
+    fn setting_value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::MazeStyle => format!("{:?}", self.game.maze_style),
+            Setting::GlyphRamp => self.game.glyph_ramp.name.clone(),
+            Setting::RenderMode => format!("{:?}", self.game.render_mode),
+            Setting::DitherMethod => format!("{:?}", self.game.dither_method),
+            Setting::Purist => on_off(self.game.purist),
+            Setting::Hud(element) => on_off(self.game.hud.shows(element)),
+        }
+    }
+    
+    /// Does what the game's unpublished events call for on disk: a level
+    /// that has started gets the ghost of the best run kept on its maze,
+    /// unless it already has one to race, and a finished level's best times
+    /// and run are saved. A replay only repeats a run that has already been
+    /// counted, so its times are never saved. Nothing that fails to save
+    /// stops the game; each failure is queued as a `SaveFailed` event.
+    pub fn save(&mut self) {
+        let mut failures = Vec::new();
+        for event in &self.game.events {
+            match event {
+                GameEvent::LevelStarted { .. } => {
+                    #[cfg(feature = "maze-map")]
+                    failures.extend(self.game.save_maze_map().err());
+                    if let (None, Some(dir)) = (&self.game.ghost, &self.save_dir) {
+                        self.game.ghost = Ghost::best(dir, self.game.current_level, self.game.maze_style, self.game.seed);
+                    }
+                }
+                GameEvent::LevelCompleted { .. } if !self.game.is_replay => {
+                    if let Some(dir) = &self.save_dir {
+                        failures.extend(GameState::save_best_times(dir, &self.game.best_times, self.game.best_total_time).err());
+                        failures.extend(ghost::save_if_best(dir, &self.game.replay).err());
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.game.events.extend(failures.into_iter().map(|error| GameEvent::SaveFailed { error }));
+    }
+    
+    /// Replaces the game with a freshly started level. Unless the run hasn't
+    /// begun, it's played straight away.
+    pub fn start(&mut self, game: GameState) {
+        self.game = game;
+        if self.flow != GameFlow::Title {
+            self.flow = GameFlow::Playing;
+        }
+    }
+    
+    pub fn render_frame(&mut self, width: usize, height: usize, clock: &dyn Clock) -> Result<String, GameError> {
+        if !(1..=MAX_FRAME_SIZE).contains(&width) || !(1..=MAX_FRAME_SIZE).contains(&height) {
+            return Err(GameError::InvalidDimensions { width, height });
+        }
+        Ok(match self.watching.as_mut() {
+            Some(player) => player.render_frame(width, height),
+            None => {
+                let frame = match self.flow {
+                    GameFlow::Playing | GameFlow::Paused(_) if self.automap => automap::render(&self.game, width, height),
+                    _ => self.game.render_frame(width, height, clock),
+                };
+                match self.flow {
+                    GameFlow::Title => {
+                        let mut lines = banner("MATRIX MAZE", width.saturating_sub(PANEL_CHROME), Font::Big);
+                        lines.extend([String::new(), "Press SPACE to start".to_string()]);
+                        panel(&frame, &lines)
+                    }
+                    GameFlow::Paused(menu) => panel(&frame, &self.menu_lines(menu, width.saturating_sub(PANEL_CHROME))),
+                    GameFlow::Failed => {
+                        let mut lines = banner("FAILED", width.saturating_sub(PANEL_CHROME), Font::Big);
+                        lines.extend([String::new(), "SPACE retries the level, ESC starts a new run".to_string()]);
+                        panel(&frame, &lines)
+                    }
+                    _ => frame,
+                }
+            }
+        })
+    }
+    
+    /// Starts watching the current level so far, from its start.
+    pub fn watch_level(&mut self) {
+        self.watching = Some(ReplayPlayer::new(self.game.replay.clone()));
+    }
+    
+    pub fn watch(&mut self, replay: Replay) {
+        self.watching = Some(ReplayPlayer::new(replay));
+    }
+    
+    pub fn control_playback(&mut self, control: PlaybackControl) -> Result<(), GameError> {
+        let player = self.watching.as_mut().ok_or_else(|| GameError::bad_state("No replay is being watched"))?;
+        if let PlaybackControl::Speed(speed) = control {
+            if speed.is_nan() {
+                return Err(GameError::bad_state("Playback speed must be a number"));
+            }
+        }
+        player.control(control);
+        Ok(())
+    }
+    
+    /// Stops watching and goes back to the game where it was left.
+    pub fn stop_watching(&mut self) {
+        self.watching = None;
+    }
+    
+    pub fn view(&self, session: SessionId) -> GameView {
+        GameView {
+            session,
+            flow: self.flow,
+            current_level: self.game.current_level,
+            maze_style: self.game.maze_style,
+            glyph_ramp: self.game.glyph_ramp.name.clone(),
+            render_mode: self.game.render_mode,
+            dither_method: self.game.dither_method,
+            dither_config: self.game.dither_config,
+            playback: self.watching.as_ref().map(|player| PlaybackView {
+                time: player.time(),
+                duration: player.replay.duration(),
+                paused: player.paused,
+                speed: player.speed,
+            }),
+        }
+    }
+    
+    /// Loads a glyph ramp by name or definition, keeping the current one if
+    /// it doesn't load.
+    pub fn set_glyph_ramp(&mut self, ramp: &str) -> Result<(), GameError> {
+        self.game.glyph_ramp = GlyphRamp::load(ramp).map_err(GameError::bad_state)?;
+        Ok(())
+    }
+}
+
+// Where `Sessions` sends the events its games queue
+type Listener = Box<dyn Fn(&SessionEvent) + Send>;
+
+fn on_off(on: bool) -> String {
+    if on { "On" } else { "Off" }.to_string()
+}
+
+// Draws `lines` centered on a frame, in a box blanked out behind them
+fn panel(frame: &str, lines: &[impl AsRef<str>]) -> String {
+    let layer = Layer {
+        border: Some(Border::Single),
+        padding: (0, 1),
+        opaque: true,
+        overflow: Overflow::Ellipsis,
+        ..Layer::new(lines.iter().map(AsRef::as_ref))
+    };
+    Overlay { layers: vec![layer] }.apply(frame)
+}
+
+/// Every session the backend is running, by ID.
+/// 
+/// The app runs one, but tests and split-screen can run several side by
+/// side, each with its own game.
+#[derive(Default)]
+pub struct Sessions {
+    sessions: HashMap<SessionId, Session>,
+    next_id: SessionId,
+    listener: Option<Listener>, // Told about every game's events
+    save_dir: Option<PathBuf>, // Given to every new session
+}
+
+impl fmt::Debug for Sessions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sessions")
+            .field("sessions", &self.sessions)
+            .field("next_id", &self.next_id)
+            .field("save_dir", &self.save_dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Sessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Sessions whose games' events are all passed to `listener`, when
+    /// `publish` is called.
+    pub fn with_listener(listener: impl Fn(&SessionEvent) + Send + 'static) -> Self {
+        Sessions { listener: Some(Box::new(listener)), ..Self::default() }
+    }
+    
+    /// These sessions, with new ones keeping their best times and runs in `dir`.
+    pub fn saving_to(self, dir: impl Into<PathBuf>) -> Self {
+        Sessions { save_dir: Some(dir.into()), ..self }
+    }
+    
+    /// Where new sessions keep their best times and runs, if anywhere.
+    pub fn save_dir(&self) -> Option<PathBuf> {
+        self.save_dir.clone()
+    }
+    
+    /// Adds a session and returns its ID. IDs are never reused.
+    pub fn open(&mut self, session: Session) -> SessionId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.sessions.insert(id, session);
+        id
+    }
+    
+    pub fn get(&self, id: SessionId) -> Option<&Session> {
+        self.sessions.get(&id)
+    }
+    
+    pub fn get_mut(&mut self, id: SessionId) -> Option<&mut Session> {
+        self.sessions.get_mut(&id)
+    }
+    
+    /// The session with ID `id`, or an error for the frontend if there's none.
+    pub fn session(&mut self, id: SessionId) -> Result<&mut Session, GameError> {
+        self.get_mut(id).ok_or_else(|| GameError::bad_state(format!("No game session {}", id)))
+    }
+    
+    /// Passes the events a session's game has queued to the listener, and
+    /// drops them if there isn't one.
+    pub fn publish(&mut self, id: SessionId) {
+        let Some(session) = self.sessions.get_mut(&id) else {
+            return;
+        };
+        for event in session.game.take_events() {
+            if let Some(listener) = &self.listener {
+                listener(&SessionEvent { session: id, event });
+            }
+        }
+    }
+    
+    pub fn close(&mut self, id: SessionId) -> Option<Session> {
+        self.sessions.remove(&id)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    
+    fn forward() -> PlayerInput {
+        PlayerInput { forward: true, delta_time: 0.05, ..PlayerInput::default() }
+    }
+    
+    #[test]
+    fn test_sessions_are_independent() {
+        let mut sessions = Sessions::new();
+        let first = sessions.open(Session::with_game(GameState::from_seed(1, MazeStyle::Blocks, 1)));
+        let second = sessions.open(Session::with_game(GameState::from_seed(1, MazeStyle::Blocks, 1)));
+        assert_ne!(first, second);
+        
+        for _ in 0..10 {
+            sessions.get_mut(first).unwrap().update(&forward()).unwrap();
+        }
+        assert!(sessions.get(first).unwrap().game.level_elapsed > 0.0);
+        assert_eq!(sessions.get(second).unwrap().game.level_elapsed, 0.0);
+        
+        sessions.close(first);
+        assert!(sessions.get(first).is_none());
+        assert_eq!(sessions.len(), 1);
+        // A closed session's ID isn't handed out again
+        assert_ne!(sessions.open(Session::with_game(GameState::from_seed(1, MazeStyle::Blocks, 1))), first);
+    }
+    
+    #[test]
+    fn test_watching_a_replay_leaves_the_game_alone() {
+        let mut session = Session::with_game(GameState::from_seed(2, MazeStyle::Blocks, 7));
+        for _ in 0..20 {
+            session.update(&forward()).unwrap();
+        }
+        let game = (session.game.player_x, session.game.player_y, session.game.level_elapsed);
+        
+        session.watch_level();
+        session.control_playback(PlaybackControl::Speed(2.0)).unwrap();
+        session.update(&forward()).unwrap();
+        let view = session.view(0);
+        assert_eq!(view.playback.unwrap().speed, 2.0);
+        assert!(view.playback.unwrap().time > 0.0);
+        assert_eq!((session.game.player_x, session.game.player_y, session.game.level_elapsed), game);
+        assert!(!session.render_frame(40, 12, &MockClock::new(0.0)).unwrap().is_empty());
+        
+        session.stop_watching();
+        assert_eq!(session.view(0).playback, None);
+    }
+    
+    #[test]
+    fn test_game_only_runs_while_playing() {
+        let mut session = Session { flow: GameFlow::Title, ..Session::with_game(GameState::from_seed(1, MazeStyle::Blocks, 5)) };
+        let clock = MockClock::new(10.0);
+        
+        session.update(&forward()).unwrap();
+        assert_eq!(session.game.level_elapsed, 0.0);
+        assert!(session.render_frame(40, 12, &clock).unwrap().contains("Press SPACE to start"));
+        
+        session.press(FlowKey::Confirm, &clock);
+        assert_eq!(session.game.level_start_time, 10.0);
+        session.update(&forward()).unwrap();
+        let elapsed = session.game.level_elapsed;
+        assert!(elapsed > 0.0);
+        
+        // Paused, the level timer stands still
+        session.press(FlowKey::Pause, &clock);
+        session.update(&forward()).unwrap();
+        assert_eq!(session.game.level_elapsed, elapsed);
+        let frame = session.render_frame(60, 16, &clock).unwrap();
+        let title = banner("PAUSED", 60, Font::Small);
+        assert!(title.iter().all(|line| frame.contains(line.as_str())) && frame.contains("> Resume <") && frame.contains("Quit to Title"));
+        session.press(FlowKey::Pause, &clock);
+        assert_eq!(session.view(0).flow, GameFlow::Playing);
+        
+        // Losing focus pauses too, and settings change while paused
+        session.press(FlowKey::FocusLost, &clock);
+        for key in [FlowKey::Down, FlowKey::Down, FlowKey::Confirm, FlowKey::Down, FlowKey::Down, FlowKey::Confirm] {
+            session.press(key, &clock);
+        }
+        assert_eq!(session.game.render_mode, RenderMode::Braille);
+        assert!(session.render_frame(60, 16, &clock).unwrap().contains("> Rendering: Braille <"));
+        assert!(!session.flow.is_running());
+    }
+    
+    #[test]
+    fn test_quitting_to_the_title_starts_a_new_run() {
+        let clock = MockClock::new(0.0);
+        let mut game = GameState::from_seed(1, MazeStyle::Blocks, 5);
+        game.level_completion_time = Some(12.0);
+        let mut session = Session::with_game(game.next_level(&clock));
+        assert_eq!(session.game.current_level, 2);
+        
+        // Quit to Title is the last item of the pause menu
+        session.press(FlowKey::Pause, &clock);
+        session.press(FlowKey::Up, &clock);
+        session.press(FlowKey::Confirm, &clock);
+        assert_eq!(session.flow, GameFlow::Title);
+        assert_eq!(session.game.current_level, 1);
+        assert!(session.game.run_times.iter().all(Option::is_none));
+    }
+    
+    #[test]
+    fn test_a_failed_level_offers_a_retry() {
+        let mut session = Session { flow: GameFlow::Failed, ..Session::with_game(GameState::from_seed(2, MazeStyle::Blocks, 5)) };
+        let clock = MockClock::new(0.0);
+        assert!(session.render_frame(80, 20, &clock).unwrap().contains("SPACE retries the level"));
+        session.press(FlowKey::Confirm, &clock);
+        assert_eq!((session.flow, session.game.current_level), (GameFlow::Playing, 2));
+    }
+    
+    #[test]
+    fn test_automap_opens_while_playing_unless_purist() {
+        let mut session = Session::with_game(GameState::from_seed(1, MazeStyle::Blocks, 5));
+        let clock = MockClock::new(0.0);
+        session.press(FlowKey::Map, &clock);
+        assert!(session.render_frame(60, 30, &clock).unwrap().contains("LEVEL 1 - MAP"));
+        session.press(FlowKey::Map, &clock);
+        assert!(!session.automap);
+        
+        session.game.purist = true;
+        session.press(FlowKey::Map, &clock);
+        assert!(!session.automap);
+    }
+}
@@ -0,0 +1,227 @@
+// Golden-file tests for rendered frames.
+//
+// Every case renders a hand-built maze from a fixed camera at a fixed size and
+// compares the text with `snapshots/<name>.txt`. When a change to the output
+// is intended, regenerate the files with
+//
+//     UPDATE_SNAPSHOTS=1 cargo test snapshots
+//
+// and read the diff before committing them.
+
+use crate::clock::MockClock;
+use crate::dither::DitherMethod;
+use crate::game::GameState;
+use crate::glyphs::GlyphRamp;
+use crate::hud::Hud;
+use crate::maze::{Maze, WallMaterial, FULL_WALL_HEIGHT, HALF_WALL_HEIGHT};
+use crate::parallel::Threading;
+use crate::renderer::{Camera, RenderMode, RenderOptions, Renderer, Sprite};
+use crate::test_mazes;
+use crate::thin_maze::ThinMaze;
+use std::fs;
+use std::path::PathBuf;
+
+// Rows of a diff shown before the rest are summarised
+const MAX_DIFF_ROWS: usize = 12;
+
+// A room with a pillar, and the exit in the middle of the east wall
+fn room() -> Maze {
+    test_mazes::room((6, 3), (10, 5))
+}
+
+// A 13x3 corridor running east through a half wall, a grate, a window and a
+// half-open door, ending in glass in front of the far wall
+fn gallery() -> Maze {
+    let mut cells = vec![vec![true; 13]; 3];
+    cells[1][1..12].fill(false);
+    let mut heights = vec![vec![FULL_WALL_HEIGHT; 13]; 3];
+    let mut materials = vec![vec![WallMaterial::Solid; 13]; 3];
+    let features = [
+        (3, HALF_WALL_HEIGHT, WallMaterial::Solid),
+        (5, FULL_WALL_HEIGHT, WallMaterial::Grate),
+        (7, FULL_WALL_HEIGHT, WallMaterial::Window),
+        (9, FULL_WALL_HEIGHT, WallMaterial::Door { open: 0.5 }),
+        (11, FULL_WALL_HEIGHT, WallMaterial::Glass),
+    ];
+    for (x, height, material) in features {
+        cells[1][x] = true;
+        heights[1][x] = height;
+        materials[1][x] = material;
+    }
+    Maze::from_layout(
+        cells,
+        heights,
+        materials,
+        (1, 1),
+        (12, 1),
+    )
+}
+
+// A 4x3 thin-wall maze with a few inner walls and the exit on the east side
+fn thin_maze() -> ThinMaze {
+    let (width, height) = (4, 3);
+    let mut north_walls = vec![vec![false; width]; height + 1];
+    north_walls[0].fill(true);
+    north_walls[height].fill(true);
+    north_walls[1][1] = true;
+    north_walls[2][2] = true;
+    let mut west_walls = vec![vec![false; width + 1]; height];
+    for row in west_walls.iter_mut() {
+        row[0] = true;
+        row[width] = true;
+    }
+    west_walls[0][2] = true;
+    west_walls[2][1] = true;
+    west_walls[1][width] = false;
+    ThinMaze {
+        width,
+        height,
+        north_walls,
+        west_walls,
+        start: (0, 1),
+        exit: (3, 1),
+    }
+}
+
+fn options(width: usize, height: usize) -> RenderOptions {
+    RenderOptions { threading: Threading::Single, ..RenderOptions::new(width, height) }
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("snapshots");
+    path.push(format!("{}.txt", name));
+    path
+}
+
+// Compares `actual` with the golden file, or rewrites the file in update mode
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = snapshot_path(name);
+    let actual = format!("{}\n", actual);
+    
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("no snapshot at {}; run with UPDATE_SNAPSHOTS=1 to create it", path.display()));
+    if expected != actual {
+        panic!("{}", describe_diff(name, &expected, &actual));
+    }
+}
+
+// Lists the rows that differ, with a caret under every changed column
+fn describe_diff(name: &str, expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let rows = expected.len().max(actual.len());
+    let changed: Vec<usize> = (0..rows).filter(|&row| expected.get(row) != actual.get(row)).collect();
+    
+    let mut report = format!(
+        "snapshot `{}` differs in {} of {} rows (- expected, + actual); if the change is intended, rerun with UPDATE_SNAPSHOTS=1\n",
+        name,
+        changed.len(),
+        rows
+    );
+    for &row in changed.iter().take(MAX_DIFF_ROWS) {
+        let old = expected.get(row).copied().unwrap_or("");
+        let new = actual.get(row).copied().unwrap_or("");
+        let (old_chars, new_chars): (Vec<char>, Vec<char>) = (old.chars().collect(), new.chars().collect());
+        let carets: String = (0..old_chars.len().max(new_chars.len()))
+            .map(|col| if old_chars.get(col) == new_chars.get(col) { ' ' } else { '^' })
+            .collect();
+        report.push_str(&format!("row {:>3} - |{}|\n        + |{}|\n           {}\n", row, old, new, carets.trim_end()));
+    }
+    if changed.len() > MAX_DIFF_ROWS {
+        report.push_str(&format!("... and {} more rows\n", changed.len() - MAX_DIFF_ROWS));
+    }
+    report
+}
+
+#[test]
+fn test_room_glyphs() {
+    let frame = Renderer::new().render(&Camera::new(2.5, 5.5, 0.3), &room(), &options(80, 24));
+    assert_snapshot("room_glyphs", &frame.to_string());
+}
+
+#[test]
+fn test_room_facing_exit() {
+    let frame = Renderer::new().render(&Camera::new(5.5, 5.5, 0.0), &room(), &options(60, 20));
+    assert_snapshot("room_facing_exit", &frame.to_string());
+}
+
+#[test]
+fn test_room_braille() {
+    let mut options = options(40, 12);
+    options.mode = RenderMode::Braille;
+    let frame = Renderer::new().render(&Camera::new(2.5, 5.5, -0.4), &room(), &options);
+    assert_snapshot("room_braille", &frame.to_string());
+}
+
+#[test]
+fn test_room_ascii_ordered() {
+    let mut options = options(60, 20);
+    options.glyph_ramp = GlyphRamp::load("ascii").unwrap();
+    options.dither_method = DitherMethod::Ordered;
+    let frame = Renderer::new().render(&Camera::new(8.5, 8.5, -2.4), &room(), &options);
+    assert_snapshot("room_ascii_ordered", &frame.to_string());
+}
+
+#[test]
+fn test_room_ghost() {
+    let camera = Camera::new(2.5, 5.5, 0.0);
+    let mut frame = Renderer::new().render(&camera, &room(), &options(80, 24));
+    let ghost = Sprite { x: 5.0, y: 5.8, width: 0.5, height: 0.8, glyph: '▒' };
+    Renderer::new().draw_sprite(&mut frame, &camera, &ghost);
+    assert_snapshot("room_ghost", &frame.to_string());
+}
+
+#[test]
+fn test_gallery_materials() {
+    let frame = Renderer::new().render(&Camera::new(1.5, 1.5, 0.0), &gallery(), &options(80, 24));
+    assert_snapshot("gallery_materials", &frame.to_string());
+}
+
+#[test]
+fn test_thin_walls() {
+    let frame = Renderer::new().render_thin(&Camera::new(0.5, 1.5, 0.2), &thin_maze(), &options(64, 20));
+    assert_snapshot("thin_walls", &frame.to_string());
+}
+
+#[test]
+fn test_game_start_message() {
+    let state = GameState::with_maze(&room());
+    let frame = state.render_frame_with(80, 24, Threading::Single, &MockClock::new(0.1));
+    assert_snapshot("game_start_message", &frame);
+}
+
+#[test]
+fn test_game_level_complete() {
+    let mut state = GameState::with_maze(&room());
+    state.player_x = 9.2;
+    state.has_won = true;
+    state.level_completion_time = Some(42.5);
+    state.best_times[0] = Some(42.5);
+    state.new_record_level = Some(1);
+    let frame = state.render_frame_with(120, 30, Threading::Single, &MockClock::new(42.5));
+    assert_snapshot("game_level_complete", &frame);
+}
+
+#[test]
+fn test_game_hud() {
+    let mut state = GameState::with_maze(&room());
+    state.hud = Hud { level: true, timer: true, best: true, compass: true, minimap: true };
+    state.level_elapsed = 12.25;
+    state.best_times[0] = Some(42.5);
+    let frame = state.render_frame_with(80, 30, Threading::Single, &MockClock::new(10.0));
+    assert_snapshot("game_hud", &frame);
+}
+
+#[test]
+fn test_describe_diff_marks_changed_columns() {
+    let report = describe_diff("example", "abc\ndef\n", "abc\ndxf\n");
+    assert!(report.contains("differs in 1 of 2 rows"));
+    assert!(report.contains("row   1 - |def|\n        + |dxf|\n            ^\n"));
+}
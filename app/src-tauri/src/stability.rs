@@ -0,0 +1,140 @@
+use crate::maze::Maze;
+use crate::renderer::{Camera, RenderOptions, Renderer};
+
+/// One leg of a scripted camera path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMove {
+    Turn(f64), // Radians, positive turns right
+    Walk(f64), // World units forward
+    Strafe(f64), // World units to the right
+}
+
+/// Flicker measured over a camera path.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StabilityReport {
+    pub frames: usize, // Consecutive frame pairs compared
+    pub surface_cells: usize, // Cells showing a dithered wall in both frames of a pair
+    pub changed_cells: usize, // Of those, cells whose glyph changed
+}
+
+impl StabilityReport {
+    /// Fraction of wall cells that changed glyph from one frame to the next.
+    /// Lower is steadier.
+    pub fn change_rate(&self) -> f64 {
+        if self.surface_cells == 0 {
+            0.0
+        } else {
+            self.changed_cells as f64 / self.surface_cells as f64
+        }
+    }
+}
+
+/// Expands a list of moves into one camera per frame, starting with `start`.
+/// 
+/// Each move is spread evenly over `frames_per_move` frames. The path
+/// ignores walls, so pick moves that stay inside open space.
+pub fn script_path(start: Camera, moves: &[CameraMove], frames_per_move: usize) -> Vec<Camera> {
+    let mut camera = start;
+    let mut path = vec![camera];
+    
+    for &step in moves {
+        for _ in 0..frames_per_move {
+            let fraction = 1.0 / frames_per_move as f64;
+            match step {
+                CameraMove::Turn(angle) => camera.angle += angle * fraction,
+                CameraMove::Walk(distance) => {
+                    camera.x += camera.angle.cos() * distance * fraction;
+                    camera.y += camera.angle.sin() * distance * fraction;
+                }
+                CameraMove::Strafe(distance) => {
+                    camera.x -= camera.angle.sin() * distance * fraction;
+                    camera.y += camera.angle.cos() * distance * fraction;
+                }
+            }
+            path.push(camera);
+        }
+    }
+    
+    path
+}
+
+/// Renders `maze` from every camera along `path` and counts how many wall
+/// cells change glyph between consecutive frames.
+/// 
+/// Walls themselves never change, so every change is the dither crawling or
+/// shimmering rather than the scene. Some change is unavoidable as walls
+/// move across the screen and their distance shading shifts, so the rate is
+/// most useful for comparing settings on the same path. A pattern fixed to
+/// the screen scores best of all, but slides over the walls instead, which
+/// this doesn't catch.
+pub fn measure(maze: &Maze, options: &RenderOptions, path: &[Camera]) -> StabilityReport {
+    let renderer = Renderer::new();
+    let frames: Vec<_> = path.iter().map(|camera| renderer.render(camera, maze, options)).collect();
+    
+    let mut report = StabilityReport::default();
+    for pair in frames.windows(2) {
+        let (before, after) = (&pair[0], &pair[1]);
+        report.frames += 1;
+        for row in 0..options.height {
+            for col in 0..options.width {
+                if before.surfaces[row][col] && after.surfaces[row][col] {
+                    report.surface_cells += 1;
+                    if before.cells[row][col] != after.cells[row][col] {
+                        report.changed_cells += 1;
+                    }
+                }
+            }
+        }
+    }
+    
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dither::UvMapping;
+    use crate::test_mazes;
+    
+    // A room with a pillar, so there are walls at several distances
+    fn room() -> Maze {
+        test_mazes::room((6, 4), (9, 9))
+    }
+    
+    #[test]
+    fn test_script_path() {
+        let start = Camera::new(1.0, 1.0, 0.0);
+        let path = script_path(start, &[CameraMove::Walk(1.0), CameraMove::Strafe(1.0)], 4);
+        assert_eq!(path.len(), 9);
+        let end = path[8];
+        assert!((end.x - 2.0).abs() < 1e-9 && (end.y - 2.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_surface_mapping_shimmers_less() {
+        let maze = room();
+        let start = Camera::new(2.5, 5.5, 0.0);
+        let moves = [
+            CameraMove::Walk(1.5),
+            CameraMove::Turn(0.6),
+            CameraMove::Strafe(-1.0),
+            CameraMove::Turn(-1.2),
+            CameraMove::Walk(-1.0),
+        ];
+        let path = script_path(start, &moves, 12);
+        
+        let mut options = RenderOptions::new(80, 30);
+        options.dither_config.uv_mapping = UvMapping::WorldRow;
+        let row_mapped = measure(&maze, &options, &path);
+        options.dither_config.uv_mapping = UvMapping::Surface;
+        let surface_mapped = measure(&maze, &options, &path);
+        
+        assert!(row_mapped.surface_cells > 10_000);
+        assert!(
+            surface_mapped.change_rate() < row_mapped.change_rate() * 0.8,
+            "surface {} vs world + row {}",
+            surface_mapped.change_rate(),
+            row_mapped.change_rate()
+        );
+    }
+}
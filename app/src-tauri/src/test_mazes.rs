@@ -0,0 +1,37 @@
+// Hand-built mazes shared by the unit and snapshot tests.
+
+use crate::maze::{Maze, WallMaterial, FULL_WALL_HEIGHT};
+
+/// A corridor `length` cells long running east along row 1, closed at both
+/// ends. The player starts at the west end and the exit is cell `exit_x` of
+/// the corridor's row.
+pub fn corridor(length: usize, exit_x: usize) -> Maze {
+    let mut cells = vec![vec![true; length]; 3];
+    cells[1][1..length - 1].fill(false);
+    Maze::from_layout(
+        cells,
+        vec![vec![FULL_WALL_HEIGHT; length]; 3],
+        vec![vec![WallMaterial::Solid; length]; 3],
+        (1, 1),
+        (exit_x, 1),
+    )
+}
+
+/// An 11x11 room with a pillar, starting at (2, 5). An exit on the outer
+/// wall gets a gap cut for it.
+pub fn room(pillar: (usize, usize), exit: (usize, usize)) -> Maze {
+    let size = 11;
+    let mut cells = vec![vec![true; size]; size];
+    for row in cells.iter_mut().take(size - 1).skip(1) {
+        row[1..size - 1].fill(false);
+    }
+    cells[pillar.1][pillar.0] = true;
+    cells[exit.1][exit.0] = false;
+    Maze::from_layout(
+        cells,
+        vec![vec![FULL_WALL_HEIGHT; size]; size],
+        vec![vec![WallMaterial::Solid; size]; size],
+        (2, 5),
+        exit,
+    )
+}
@@ -0,0 +1,269 @@
+use crate::maze::{random_seed, Maze, WallMaterial, FULL_WALL_HEIGHT};
+use serde::{Deserialize, Serialize};
+
+// Walls are thin slabs centred on cell edges
+pub const WALL_THICKNESS: f64 = 0.12;
+// How close the player's centre may get to a wall face
+const PLAYER_RADIUS: f64 = 0.15;
+
+/// An edge-based maze: every cell is walkable and walls are thin segments
+/// along the edges between cells.
+/// 
+/// This is the model classic maze algorithms are stated in. Compared to the
+/// block model in `Maze`, where walls take up whole cells, the same grid size
+/// holds roughly four times as many corridors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThinMaze {
+    pub width: usize,
+    pub height: usize,
+    pub north_walls: Vec<Vec<bool>>, // (height + 1) x width - wall along the top edge of cell (x, y); the last row is the bottom boundary
+    pub west_walls: Vec<Vec<bool>>, // height x (width + 1) - wall along the left edge of cell (x, y); the last column is the right boundary
+    pub start: (usize, usize), // Starting cell
+    pub exit: (usize, usize), // Boundary cell whose outer edge is open
+}
+
+impl ThinMaze {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::with_seed(width, height, random_seed())
+    }
+    
+    /// Generates the maze `seed` describes. The same size and seed always
+    /// give the same layout, start and exit.
+    pub fn with_seed(width: usize, height: usize, seed: u64) -> Self {
+        let mut maze = ThinMaze {
+            width,
+            height,
+            north_walls: vec![vec![true; width]; height + 1],
+            west_walls: vec![vec![true; width + 1]; height],
+            start: (0, 0),
+            exit: (width - 1, height - 1),
+        };
+        maze.generate(seed);
+        maze
+    }
+    
+    fn generate(&mut self, mut rng_seed: u64) {
+        // Pick a random boundary cell and open its outer edge
+        let edge = next_random(&mut rng_seed) % 4;
+        self.exit = match edge {
+            0 => (next_random(&mut rng_seed) % self.width, 0),
+            1 => (self.width - 1, next_random(&mut rng_seed) % self.height),
+            2 => (next_random(&mut rng_seed) % self.width, self.height - 1),
+            _ => (0, next_random(&mut rng_seed) % self.height),
+        };
+        let (ex, ey) = self.exit;
+        match edge {
+            0 => self.north_walls[0][ex] = false,
+            1 => self.west_walls[ey][self.width] = false,
+            2 => self.north_walls[self.height][ex] = false,
+            _ => self.west_walls[ey][0] = false,
+        }
+        
+        // Start somewhere not too close to the exit
+        let mut attempts = 0;
+        loop {
+            let x = next_random(&mut rng_seed) % self.width;
+            let y = next_random(&mut rng_seed) % self.height;
+            let dist_to_exit = ((x as f64 - ex as f64).powi(2) + (y as f64 - ey as f64).powi(2)).sqrt();
+            if dist_to_exit > 3.0 || attempts > 50 {
+                self.start = (x, y);
+                break;
+            }
+            attempts += 1;
+        }
+        
+        // Recursive backtracking, one cell at a time - every cell is a corridor
+        let mut visited = vec![vec![false; self.width]; self.height];
+        let mut stack = vec![self.start];
+        visited[self.start.1][self.start.0] = true;
+        
+        while let Some(current) = stack.pop() {
+            let (x, y) = current;
+            let mut neighbors = Vec::new();
+            if x > 0 && !visited[y][x - 1] {
+                neighbors.push((x - 1, y));
+            }
+            if x + 1 < self.width && !visited[y][x + 1] {
+                neighbors.push((x + 1, y));
+            }
+            if y > 0 && !visited[y - 1][x] {
+                neighbors.push((x, y - 1));
+            }
+            if y + 1 < self.height && !visited[y + 1][x] {
+                neighbors.push((x, y + 1));
+            }
+            
+            if !neighbors.is_empty() {
+                stack.push(current);
+                let next = neighbors[next_random(&mut rng_seed) % neighbors.len()];
+                self.remove_wall_between(current, next);
+                visited[next.1][next.0] = true;
+                stack.push(next);
+            }
+        }
+    }
+    
+    fn remove_wall_between(&mut self, a: (usize, usize), b: (usize, usize)) {
+        if a.0 != b.0 {
+            self.west_walls[a.1][a.0.max(b.0)] = false;
+        } else {
+            self.north_walls[a.1.max(b.1)][a.0] = false;
+        }
+    }
+    
+    /// True if there is a wall along the top edge of cell (x, y).
+    /// Edges outside the maze count as walls.
+    pub fn has_north_wall(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize > self.height {
+            return true;
+        }
+        self.north_walls[y as usize][x as usize]
+    }
+    
+    /// True if there is a wall along the left edge of cell (x, y).
+    /// Edges outside the maze count as walls.
+    pub fn has_west_wall(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x as usize > self.width || y as usize >= self.height {
+            return true;
+        }
+        self.west_walls[y as usize][x as usize]
+    }
+    
+    /// True if a player centred at (x, y) would overlap a wall, or is outside
+    /// the maze.
+    pub fn is_blocked(&self, x: f64, y: f64) -> bool {
+        if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+            return true;
+        }
+        
+        let cx = x.floor() as i32;
+        let cy = y.floor() as i32;
+        let fx = x - cx as f64;
+        let fy = y - cy as f64;
+        let margin = WALL_THICKNESS / 2.0 + PLAYER_RADIUS;
+        
+        let near_west = fx < margin;
+        let near_east = fx > 1.0 - margin;
+        let near_north = fy < margin;
+        let near_south = fy > 1.0 - margin;
+        
+        // Faces of the cell's own walls
+        if (near_west && self.has_west_wall(cx, cy))
+            || (near_east && self.has_west_wall(cx + 1, cy))
+            || (near_north && self.has_north_wall(cx, cy))
+            || (near_south && self.has_north_wall(cx, cy + 1))
+        {
+            return true;
+        }
+        
+        // Ends of walls that meet at the nearest corner, from neighbouring cells
+        if (near_west || near_east) && (near_north || near_south) {
+            let corner_x = if near_east { cx + 1 } else { cx };
+            let corner_y = if near_south { cy + 1 } else { cy };
+            return self.has_north_wall(corner_x - 1, corner_y)
+                || self.has_north_wall(corner_x, corner_y)
+                || self.has_west_wall(corner_x, corner_y - 1)
+                || self.has_west_wall(corner_x, corner_y);
+        }
+        
+        false
+    }
+    
+    /// Expands the maze into the block model, where cell (x, y) becomes block
+    /// (2x + 1, 2y + 1) and each wall segment becomes a wall block.
+    pub fn to_block_maze(&self) -> Maze {
+        let width = self.width * 2 + 1;
+        let height = self.height * 2 + 1;
+        let mut cells = vec![vec![true; width]; height];
+        
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (bx, by) = (x * 2 + 1, y * 2 + 1);
+                cells[by][bx] = false;
+                if !self.north_walls[y][x] {
+                    cells[by - 1][bx] = false;
+                }
+                if !self.west_walls[y][x] {
+                    cells[by][bx - 1] = false;
+                }
+                if y + 1 == self.height && !self.north_walls[self.height][x] {
+                    cells[by + 1][bx] = false;
+                }
+                if x + 1 == self.width && !self.west_walls[y][self.width] {
+                    cells[by][bx + 1] = false;
+                }
+            }
+        }
+        
+        // The block model puts the exit on the opened boundary block itself
+        let (ex, ey) = (self.exit.0 * 2 + 1, self.exit.1 * 2 + 1);
+        let exit = if !self.north_walls[0][self.exit.0] && self.exit.1 == 0 {
+            (ex, 0)
+        } else if !self.north_walls[self.height][self.exit.0] && self.exit.1 + 1 == self.height {
+            (ex, height - 1)
+        } else if !self.west_walls[self.exit.1][0] && self.exit.0 == 0 {
+            (0, ey)
+        } else {
+            (width - 1, ey)
+        };
+        
+        Maze::from_layout(
+            cells,
+            vec![vec![FULL_WALL_HEIGHT; width]; height],
+            vec![vec![WallMaterial::Solid; width]; height],
+            (self.start.0 * 2 + 1, self.start.1 * 2 + 1),
+            exit,
+        )
+    }
+}
+
+// Simple LCG step, same constants as the block maze generator.
+// The low bits of an LCG cycle quickly, so only the high bits are used.
+fn next_random(rng_seed: &mut u64) -> usize {
+    *rng_seed = rng_seed.wrapping_mul(1103515245).wrapping_add(12345);
+    (*rng_seed >> 33) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_every_cell_reachable() {
+        let maze = ThinMaze::with_seed(9, 7, 0x5eed);
+        let mut seen = vec![vec![false; 9]; 7];
+        let mut stack = vec![maze.start];
+        seen[maze.start.1][maze.start.0] = true;
+        
+        while let Some((x, y)) = stack.pop() {
+            let (xi, yi) = (x as i32, y as i32);
+            let moves = [
+                (!maze.has_west_wall(xi, yi), xi - 1, yi),
+                (!maze.has_west_wall(xi + 1, yi), xi + 1, yi),
+                (!maze.has_north_wall(xi, yi), xi, yi - 1),
+                (!maze.has_north_wall(xi, yi + 1), xi, yi + 1),
+            ];
+            for (open, nx, ny) in moves {
+                if open && nx >= 0 && ny >= 0 && (nx as usize) < 9 && (ny as usize) < 7 && !seen[ny as usize][nx as usize] {
+                    seen[ny as usize][nx as usize] = true;
+                    stack.push((nx as usize, ny as usize));
+                }
+            }
+        }
+        
+        assert!(seen.iter().flatten().all(|&s| s));
+    }
+    
+    #[test]
+    fn test_blocked_near_walls() {
+        let maze = ThinMaze::with_seed(8, 8, 0x5eed);
+        let (x, y) = (maze.start.0 as f64, maze.start.1 as f64);
+        
+        // The centre of a cell is always free
+        assert!(!maze.is_blocked(x + 0.5, y + 0.5));
+        
+        // Right up against an edge is blocked only if that edge has a wall
+        let against_west = maze.is_blocked(x + 0.05, y + 0.5);
+        assert_eq!(against_west, maze.has_west_wall(maze.start.0 as i32, maze.start.1 as i32));
+    }
+}
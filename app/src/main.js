@@ -1,4 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
 import { LEVEL_COLORS } from './constants.js';
 
 // Initialize colors from constants - sets CSS variables
@@ -17,7 +19,10 @@ function updateColorRgbValues() {
     });
 }
 
-let gameState = null;
+// The game lives in the backend; the frontend keeps its session ID and the
+// latest view of it (game flow, level, display settings, replay playback)
+let session = null;
+let view = null;
 let keys = {
     w: false,
     s: false,
@@ -30,6 +35,10 @@ let keys = {
 let mouseDeltaX = 0.0;
 let lastFrameTime = null;
 
+// Seconds skipped by the arrow keys while watching a replay
+const REPLAY_SEEK_SECONDS = 5;
+const TICK_RATE = 120;
+
 let viewport = null;
 let levelIndicator = null;
 let controls = null;
@@ -70,15 +79,8 @@ async function init() {
         setTimeout(() => {
             if (viewport && document.activeElement === document.body) {
                 // Check if game is won - if so, refocus viewport for spacebar input
-                try {
-                    if (gameState) {
-                        const gameStateObj = JSON.parse(gameState);
-                        if (gameStateObj && gameStateObj.has_won) {
-                            viewport.focus();
-                        }
-                    }
-                } catch (e) {
-                    // Ignore parse errors
+                if (view && view.flow !== 'Playing') {
+                    viewport.focus();
                 }
             }
         }, 100);
@@ -101,9 +103,16 @@ async function init() {
     });
     
     try {
-        const stateJson = await invoke('init_game');
-        gameState = stateJson;
-        console.log('Game initialized, state:', stateJson.substring(0, 100));
+        await listen('game-event', ({ payload }) => handleGameEvent(payload));
+        // Pause when the window goes into the background, so the level isn't left running
+        await getCurrentWindow().onFocusChanged(({ payload: focused }) => {
+            if (!focused) {
+                pressKey('FocusLost');
+            }
+        });
+        view = await invoke('init_game');
+        session = view.session;
+        console.log('Game initialized, session:', session);
         lastFrameTime = performance.now() / 1000.0; // Initialize frame time
         resizeViewport();
         gameLoop();
@@ -112,6 +121,26 @@ async function init() {
     }
 }
 
+// Reacts to what happened in the game, as the backend reports it
+function handleGameEvent(event) {
+    // Events for the session being opened can arrive before its ID does
+    if (session !== null && event.session !== session) return;
+    switch (event.type) {
+        case 'LevelCompleted':
+            console.log(`Level ${event.level} completed in ${event.time.toFixed(2)}s`);
+            if (viewport) {
+                viewport.focus();
+            }
+            break;
+        case 'RecordBroken':
+            console.log('New record:', event.record, event.time.toFixed(2));
+            break;
+        case 'SaveFailed':
+            console.warn('Failed to save:', event.error);
+            break;
+    }
+}
+
 function resizeViewport() {
     if (!viewport) return;
     
@@ -164,7 +193,7 @@ function resizeViewport() {
 }
 
 async function gameLoop() {
-    if (!gameState) return;
+    if (session === null) return;
     
     // Calculate delta time for frame-rate independent movement
     const currentTime = performance.now() / 1000.0; // Convert to seconds
@@ -174,23 +203,15 @@ async function gameLoop() {
     }
     lastFrameTime = currentTime;
     
-    // Parse game state to check if won
-    let gameStateObj = null;
-    try {
-        gameStateObj = JSON.parse(gameState);
-    } catch (e) {
-        // If parsing fails, continue with update
-    }
-    
-    // Get input (only if not won)
+    // Get input; the backend ignores it unless the game is being played
     const input = {
-        forward: gameStateObj?.has_won ? false : keys.w,
-        backward: gameStateObj?.has_won ? false : keys.s,
-        left: gameStateObj?.has_won ? false : keys.a,
-        right: gameStateObj?.has_won ? false : keys.d,
-        turn_left: gameStateObj?.has_won ? false : keys.q,
-        turn_right: gameStateObj?.has_won ? false : keys.e,
-        mouse_delta_x: gameStateObj?.has_won ? 0.0 : mouseDeltaX,
+        forward: keys.w,
+        backward: keys.s,
+        left: keys.a,
+        right: keys.d,
+        turn_left: keys.q,
+        turn_right: keys.e,
+        mouse_delta_x: mouseDeltaX,
         delta_time: deltaTime,
     };
     
@@ -199,21 +220,14 @@ async function gameLoop() {
     
     // Update game state
     try {
-        gameState = await invoke('update_game', {
-            stateJson: gameState,
-            input: input,
-        });
+        view = await invoke('update_game', { session: session, input: input });
         
-        // Render frame (returns [frame, updatedState])
-        const [frame, updatedState] = await invoke('render_frame', {
-            stateJson: gameState,
+        const frame = await invoke('render_frame', {
+            session: session,
             width: viewportWidth,
             height: viewportHeight,
         });
         
-        // Update game state in case freeze frame was captured
-        gameState = updatedState;
-        
         // Debug: log first 100 chars of frame
         if (frame && frame.length > 0) {
             console.log('Frame preview:', frame.substring(0, 100));
@@ -223,14 +237,37 @@ async function gameLoop() {
         
         // Display frame
         displayFrame(frame);
+        
+        if (view.playback && levelIndicator) {
+            const status = view.playback.paused ? 'paused' : `${view.playback.speed}x`;
+            levelIndicator.textContent = `Replay ${view.playback.time.toFixed(1)}s (${status})`;
+        }
     } catch (error) {
         console.error('Game loop error:', error);
-        console.error('Game state:', gameState);
     }
     
     requestAnimationFrame(gameLoop);
 }
 
+// Forwards a key to the backend's game flow (Confirm, Pause, Restart, Up, Down or FocusLost)
+async function pressKey(key) {
+    if (session === null) return;
+    try {
+        view = await invoke('press_key', { session: session, key: key });
+    } catch (error) {
+        console.error(`Error pressing ${key}:`, error);
+    }
+}
+
+// Sends a playback control (pause, speed, seek) to the replay being watched
+async function controlReplay(control) {
+    try {
+        view = await invoke('control_replay', { session: session, control: control });
+    } catch (error) {
+        console.error('Error controlling replay:', error);
+    }
+}
+
 function displayFrame(frame) {
     if (!viewport) return;
     
@@ -275,63 +312,82 @@ function displayFrame(frame) {
     viewport.textContent = frame;
     
     // Update level indicator, viewport, and controls color class
-    if (gameState) {
-        try {
-            const gameStateObj = JSON.parse(gameState);
-            const level = gameStateObj.current_level || 1;
-            if (levelIndicator) {
-                levelIndicator.textContent = `Level ${level}`;
-                levelIndicator.className = `level-${level}`;
-            }
-            viewport.className = `level-${level}`;
-            if (controls) {
-                controls.className = `level-${level}`;
-            }
-            
-            // Ensure viewport maintains focus, especially on win screen
-            // This helps ensure spacebar presses are registered
-            if (gameStateObj.has_won && document.activeElement !== viewport) {
-                viewport.focus();
-            }
-        } catch (e) {
-            // Ignore parse errors
+    if (view) {
+        const level = view.current_level || 1;
+        if (levelIndicator) {
+            levelIndicator.textContent = `Level ${level}`;
+            levelIndicator.className = `level-${level}`;
+        }
+        viewport.className = `level-${level}`;
+        if (controls) {
+            controls.className = `level-${level}`;
+        }
+        
+        // Ensure viewport maintains focus, especially on win screen
+        // This helps ensure spacebar presses are registered
+        if (view.flow !== 'Playing' && document.activeElement !== viewport) {
+            viewport.focus();
         }
     }
 }
 
 // Keyboard event handlers - listen on window to catch all keys
 window.addEventListener('keydown', async (e) => {
-    // Check if game is won and space is pressed for restart
-    if (e.key === ' ' || e.key === 'Spacebar') {
-        try {
-            // Parse game state
-            let gameStateObj = null;
-            try {
-                gameStateObj = JSON.parse(gameState);
-            } catch (err) {
-                return;
-            }
-            
-            // Only proceed if game is won
-            if (gameStateObj && gameStateObj.has_won) {
-                e.preventDefault();
-                
-                // Advance to next level or restart
-                gameState = await invoke('next_level', { stateJson: gameState });
-                console.log('Advanced to next level or restarted');
-                
-                // Ensure viewport maintains focus after level transition
-                if (viewport) {
-                    viewport.focus();
+    // While a replay is playing, keys control playback instead of the player
+    if (view && view.playback) {
+        const playback = view.playback;
+        const seekTicks = REPLAY_SEEK_SECONDS * TICK_RATE;
+        const tick = Math.round(playback.time * TICK_RATE);
+        switch (e.key.toLowerCase()) {
+            case ' ':
+                await controlReplay('TogglePause');
+                break;
+            case 'f':
+                await controlReplay({ Speed: playback.speed === 2 ? 1 : 2 });
+                break;
+            case 'arrowleft':
+                await controlReplay({ Seek: Math.max(0, tick - seekTicks) });
+                break;
+            case 'arrowright':
+                await controlReplay({ Seek: tick + seekTicks });
+                break;
+            case 'p':
+            case 'escape':
+                try {
+                    view = await invoke('stop_replay', { session: session });
+                } catch (error) {
+                    console.error('Error stopping replay:', error);
                 }
-                return;
+                break;
+        }
+        e.preventDefault();
+        return;
+    }
+    
+    // Space moves the game along (start, next level, menu choice); the backend
+    // decides what it means right now, so a stale view can't get it wrong
+    if (e.key === ' ' || e.key === 'Spacebar' || e.key === 'Enter') {
+        e.preventDefault();
+        if (!e.repeat) {
+            await pressKey('Confirm');
+            
+            // Ensure viewport maintains focus after level transition
+            if (viewport) {
+                viewport.focus();
             }
-        } catch (error) {
-            console.error('Error handling spacebar:', error);
         }
+        return;
     }
     
     switch (e.key.toLowerCase()) {
+        case 'arrowup':
+            await pressKey('Up');
+            e.preventDefault();
+            break;
+        case 'arrowdown':
+            await pressKey('Down');
+            e.preventDefault();
+            break;
         case 'w':
             keys.w = true;
             e.preventDefault();
@@ -356,12 +412,107 @@ window.addEventListener('keydown', async (e) => {
             keys.e = true;
             e.preventDefault();
             break;
+        case 't':
+            // Toggle between block and thin-wall mazes (regenerates the current level)
+            if (!e.repeat && view) {
+                try {
+                    const style = view.maze_style === 'ThinWalls' ? 'Blocks' : 'ThinWalls';
+                    view = await invoke('set_maze_style', { session: session, style: style });
+                } catch (error) {
+                    console.error('Error switching maze style:', error);
+                }
+            }
+            e.preventDefault();
+            break;
+        case 'g':
+            // Cycle through the built-in glyph ramps
+            if (!e.repeat && view) {
+                try {
+                    const ramps = ['blocks', 'ascii', 'braille', 'katakana'];
+                    const current = ramps.indexOf(view.glyph_ramp);
+                    const ramp = ramps[(current + 1) % ramps.length];
+                    view = await invoke('set_glyph_ramp', { session: session, ramp: ramp });
+                } catch (error) {
+                    console.error('Error switching glyph ramp:', error);
+                }
+            }
+            e.preventDefault();
+            break;
+        case 'b':
+            // Toggle braille rendering (2x4 dots per character)
+            if (!e.repeat && view) {
+                try {
+                    const mode = view.render_mode === 'Braille' ? 'Glyphs' : 'Braille';
+                    view = await invoke('set_render_mode', { session: session, mode: mode });
+                } catch (error) {
+                    console.error('Error switching render mode:', error);
+                }
+            }
+            e.preventDefault();
+            break;
+        case 'n':
+            // Cycle dithering algorithms, to compare how stable each looks while moving
+            if (!e.repeat && view) {
+                try {
+                    const methods = ['Fractal', 'Ordered', 'BlueNoise', 'FloydSteinberg', 'Atkinson'];
+                    const current = methods.indexOf(view.dither_method);
+                    const method = methods[(current + 1) % methods.length];
+                    view = await invoke('set_dither_method', { session: session, method: method });
+                } catch (error) {
+                    console.error('Error switching dither method:', error);
+                }
+            }
+            e.preventDefault();
+            break;
+        case 'm':
+            // Open or close the automap; the backend refuses in purist mode
+            if (!e.repeat) {
+                await pressKey('Map');
+            }
+            e.preventDefault();
+            break;
+        case 'c':
+            // Play the level again on the same maze, racing the ghost of the best run on it
+            if (!e.repeat) {
+                await pressKey('Restart');
+            }
+            e.preventDefault();
+            break;
+        case 'r':
+            // Save the current level's replay to a file
+            if (!e.repeat && view) {
+                try {
+                    const path = await invoke('save_replay', { session: session });
+                    console.log('Replay saved to', path);
+                } catch (error) {
+                    console.error('Error saving replay:', error);
+                }
+            }
+            e.preventDefault();
+            break;
+        case 'p':
+            // Watch the current level so far, from its start
+            if (!e.repeat && view) {
+                try {
+                    view = await invoke('watch_replay', { session: session });
+                    keys = { w: false, s: false, a: false, d: false, q: false, e: false };
+                } catch (error) {
+                    console.error('Error starting replay:', error);
+                }
+            }
+            e.preventDefault();
+            break;
         case 'escape':
             // Unlock pointer if locked
             if (document.pointerLockElement) {
                 document.exitPointerLock();
+            }
+            if (view && view.flow !== 'Title') {
+                if (!e.repeat) {
+                    await pressKey('Pause');
+                }
             } else {
-                // Tauri 2.x way to close window
+                // Escape on the title screen quits; Tauri 2.x way to close window
                 import('@tauri-apps/api/window').then(({ appWindow }) => {
                     appWindow.close();
                 }).catch(() => {
@@ -397,6 +548,47 @@ window.addEventListener('keyup', (e) => {
     }
 });
 
+// Tune the fractal dither live from the devtools console, e.g.
+// setDitherConfig({ contrast: 1.4, max_level: 4, uv_mapping: 'Screen' })
+// Only the given fields change; the rest keep their current values.
+window.setDitherConfig = async (changes) => {
+    if (!view) {
+        return;
+    }
+    try {
+        const config = { ...view.dither_config, ...changes };
+        view = await invoke('set_dither_config', { session: session, config: config });
+        return view.dither_config;
+    } catch (error) {
+        console.error('Error setting dither config:', error);
+    }
+};
+
+// Watch a shared replay from the devtools console, e.g. playReplay(text)
+// with the contents of a file from app/replays/
+window.playReplay = async (text) => {
+    if (!view) {
+        return;
+    }
+    try {
+        view = await invoke('load_replay', { session: session, replay: text });
+    } catch (error) {
+        console.error('Error loading replay:', error);
+    }
+};
+
+// Race a shared replay's ghost from the devtools console, e.g. raceReplay(text)
+window.raceReplay = async (text) => {
+    if (!view) {
+        return;
+    }
+    try {
+        view = await invoke('race_replay', { session: session, replay: text });
+    } catch (error) {
+        console.error('Error starting race:', error);
+    }
+};
+
 // Handle window resize
 window.addEventListener('resize', () => {
     resizeViewport();